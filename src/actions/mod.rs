@@ -27,6 +27,9 @@ pub struct Actions {
     pub player_movement: Vec2,
     pub jump: bool,
     pub attack: bool,
+    pub magnet: bool,
+    /// Pressed down, for buying doors with grain
+    pub interact: bool,
     pub pause: bool,
     pub back: bool,
 }
@@ -113,4 +116,27 @@ pub fn set_movement_actions(
             || gamepad_buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::West))
             || gamepad_buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::East));
     }
+
+    actions.interact =
+        keyboard_input.just_pressed(KeyCode::S) || keyboard_input.just_pressed(KeyCode::Down);
+
+    for gamepad in gamepad_input.iter() {
+        if actions.interact {
+            break;
+        }
+        actions.interact =
+            gamepad_buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::DPadDown));
+    }
+
+    actions.magnet =
+        keyboard_input.pressed(KeyCode::LShift) || keyboard_input.pressed(KeyCode::F);
+
+    for gamepad in gamepad_input.iter() {
+        if actions.magnet {
+            break;
+        }
+        actions.magnet = actions.magnet
+            || gamepad_buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::North))
+            || gamepad_buttons.pressed(GamepadButton::new(gamepad, GamepadButtonType::RightTrigger));
+    }
 }
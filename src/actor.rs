@@ -1,9 +1,10 @@
 use std::f32::consts::PI;
 
 use crate::{
-    pickup::{check_for_pickups, PickupCollector, PickupEvent},
-    soul::CollectedSoulEvent,
+    pickup::{check_for_pickups, PickupCollector, PickupEvent, PickupType},
     sprite_anim::{EffectBundle, SpriteAnimator},
+    world::Surface,
+    zone::ZoneEffects,
     GameState,
 };
 use bevy::{prelude::*, sprite::Anchor};
@@ -29,6 +30,8 @@ pub struct Actor {
     pub attack_input: Option<Vec2>,
     pub attack_time: f32,
     pub attack_range: f32,
+    /// Extra jumps the actor can make before landing again
+    pub air_jumps: usize,
 }
 
 #[derive(Component, Default, Clone)]
@@ -37,12 +40,17 @@ pub struct ActorStatus {
     pub velocity: Vec2,
     pub facing_left: bool,
     pub air_timer: f32,
+    pub air_jumps_used: usize,
     pub attack_direction: Option<Vec2>,
     pub attack_timer: f32,
     pub left_wall: bool,
     pub right_wall: bool,
     pub event: Option<ActorEvent>,
     pub last_dt: f32,
+    /// What the actor is standing on, or last stood on while in the air
+    pub surface: Surface,
+    /// Wind, water and gravity from the zones the actor is in
+    pub zone: ZoneEffects,
 }
 
 #[derive(Component, Default, Clone)]
@@ -52,12 +60,14 @@ pub struct ActorAnimationStates {
     pub jump_row: usize,
     pub fall_row: usize,
     pub attack_row: usize,
+    pub swim_row: usize,
 }
 
 #[derive(Component, Default, Clone)]
 pub struct ActorAudio {
     pub jump: Handle<AudioSource>,
     pub land: Handle<AudioSource>,
+    pub bounce: Handle<AudioSource>,
     pub attack: Handle<AudioSource>,
     pub hit: Handle<AudioSource>,
     pub death: Handle<AudioSource>,
@@ -204,6 +214,7 @@ impl Default for Actor {
             attack_sprite: None,
             can_jump: false,
             can_attack: false,
+            air_jumps: 0,
         }
     }
 }
@@ -212,10 +223,11 @@ pub fn actor_status(
     time: Res<Time>,
     mut actor_query: Query<(
         Entity,
-        &Transform,
+        &GlobalTransform,
         &mut ActorStatus,
         &KinematicCharacterControllerOutput,
     )>,
+    surface_query: Query<&Surface>,
     rapier_context: Res<RapierContext>,
 ) {
     for (entity, transform, mut actor_status, controller_output) in &mut actor_query {
@@ -228,17 +240,20 @@ pub fn actor_status(
         }
 
         actor_status.grounded = controller_output.grounded;
-        actor_status.velocity = controller_output.effective_translation / actor_status.last_dt;
+        // Wind only drifts actors along, it shouldn't build up in their own velocity
+        actor_status.velocity =
+            controller_output.effective_translation / actor_status.last_dt - actor_status.zone.wind;
 
         if actor_status.grounded {
             actor_status.air_timer = 0.;
+            actor_status.air_jumps_used = 0;
             actor_status.velocity.y = 0.;
         } else {
             actor_status.air_timer += time.delta_seconds();
         }
 
         let shape = Collider::capsule_y(4.5, 4.5);
-        let shape_pos = transform.translation.truncate();
+        let shape_pos = transform.translation().truncate();
         let filter = QueryFilter::new()
             .exclude_sensors()
             .exclude_collider(entity);
@@ -259,22 +274,37 @@ pub fn actor_status(
         } else {
             actor_status.left_wall = false;
         }
+
+        if actor_status.grounded {
+            actor_status.surface = rapier_context
+                .cast_shape(shape_pos, 0., Vec2::new(0., -distance), &shape, 1., filter)
+                .and_then(|(ground, _)| surface_query.get(ground).ok())
+                .copied()
+                .unwrap_or_default();
+        }
     }
 }
 
 pub fn actor_pickup_effects(
-    mut soul_pickup_events: EventReader<CollectedSoulEvent>,
+    mut pickup_events: EventReader<PickupEvent>,
     mut actor_statuses: Query<(&mut ActorStatus, &ActorEffects), With<PickupCollector>>,
     mut commands: Commands,
 ) {
-    for ev in soul_pickup_events.iter() {
+    for ev in pickup_events.iter() {
         if let Ok((mut status, fx)) = actor_statuses.get_mut(ev.collector_entity) {
-            status.event = Some(ActorEvent::Pickup);
+            // Other pickup types play their own sound
+            if let PickupType::Soul { .. } = ev.pickup_type {
+                status.event = Some(ActorEvent::Pickup);
+            }
 
             commands.spawn(EffectBundle {
                 sprite_sheet_bundle: SpriteSheetBundle {
                     texture_atlas: fx.pickup.clone(),
-                    transform: Transform::from_translation(ev.pickup_pos),
+                    sprite: TextureAtlasSprite {
+                        color: ev.pickup_type.effect_color(),
+                        ..Default::default()
+                    },
+                    transform: Transform::from_translation(ev.pickup_position),
                     ..Default::default()
                 },
                 sprite_animator: SpriteAnimator::new(0, 3, 4, 0.05, false, true),
@@ -285,7 +315,7 @@ pub fn actor_pickup_effects(
 }
 
 pub fn actor_attack(
-    mut actor_query: Query<(&Transform, &Actor, &mut ActorStatus)>,
+    mut actor_query: Query<(&GlobalTransform, &Actor, &mut ActorStatus)>,
     mut target_query: Query<&mut Scythable>,
     rapier_context: Res<RapierContext>,
 ) {
@@ -301,14 +331,14 @@ pub fn actor_attack(
             let filter = QueryFilter::new();
 
             rapier_context.intersections_with_shape(
-                transform.translation.truncate() + attack_offset,
+                transform.translation().truncate() + attack_offset,
                 0.,
                 &shape,
                 filter,
                 |entity| -> bool {
                     if let Ok(mut target) = target_query.get_mut(entity) {
                         target.scythed = true;
-                        target.hit_from = Some(transform.translation.truncate());
+                        target.hit_from = Some(transform.translation().truncate());
                         status.event = Some(ActorEvent::Hit);
                     }
                     true
@@ -330,9 +360,16 @@ pub fn actor_movement(
     mut actor_query: Query<(&Actor, &mut ActorStatus, &mut KinematicCharacterController)>,
 ) {
     for (actor, mut status, mut controller) in &mut actor_query {
+        // Only the ground changes how actors run, jumps keep the surface they left from
+        let ground = if status.grounded {
+            status.surface
+        } else {
+            Surface::Normal
+        };
+
         let dir_match = actor.move_input.signum() == status.velocity.x.signum();
         let accel = if dir_match { actor.accel } else { actor.deccel };
-        status.velocity.x += actor.move_input * accel * time.delta_seconds();
+        status.velocity.x += actor.move_input * accel * ground.accel_scale() * time.delta_seconds();
 
         // Track facing based on input seperately
         if actor.move_input > 0.1 {
@@ -342,10 +379,11 @@ pub fn actor_movement(
         }
 
         if actor.move_input.abs() < 0.1 {
-            status.velocity.x *= 1.0 - actor.drag;
+            status.velocity.x *= 1.0 - actor.drag * ground.drag_scale();
         }
 
-        status.velocity.x = status.velocity.x.clamp(-actor.move_speed, actor.move_speed);
+        let move_speed = actor.move_speed * ground.speed_scale();
+        status.velocity.x = status.velocity.x.clamp(-move_speed, move_speed);
 
         if (status.velocity.x > 0. && status.right_wall)
             || (status.velocity.x < 0. && status.left_wall)
@@ -354,20 +392,38 @@ pub fn actor_movement(
         }
 
         if actor.can_jump && actor.jump_input {
-            status.velocity.y = actor.jump_speed;
+            status.velocity.y = actor.jump_speed * status.surface.jump_scale();
 
             if status.grounded {
                 status.event = Some(ActorEvent::Launched);
             }
         } else if !status.grounded {
-            status.velocity.y -= if status.velocity.y > 0. {
+            let gravity = if status.velocity.y > 0. {
                 actor.down_gravity
             } else {
                 actor.up_gravity
-            } * time.delta_seconds();
+            };
+            status.velocity.y -= gravity * status.zone.gravity_scale * time.delta_seconds();
+        }
+
+        if let Some(water) = status.zone.water {
+            status.velocity.y += water.buoyancy * time.delta_seconds();
+            status.velocity *= 1. - (water.drag * time.delta_seconds()).min(1.);
+
+            // Swimming up works any time, not just off the ground
+            if actor.jump_input {
+                let swim_speed = actor.jump_speed * ZoneEffects::SWIM_SCALE;
+                status.velocity.y = status.velocity.y.max(swim_speed);
+            }
+        }
+
+        if status.grounded && status.surface == Surface::Bounce {
+            let bounce_speed = actor.jump_speed * Surface::BOUNCE_SCALE;
+            status.velocity.y = status.velocity.y.max(bounce_speed);
+            status.event = Some(ActorEvent::Launched);
         }
 
-        controller.translation = Some(time.delta_seconds() * status.velocity);
+        controller.translation = Some(time.delta_seconds() * (status.velocity + status.zone.wind));
         status.last_dt = time.delta_seconds();
     }
 }
@@ -390,7 +446,9 @@ fn actor_animations(
     >,
 ) {
     for (actor, status, anim_states, mut animator, mut sprite, opt_children) in &mut actor_query {
-         if status.grounded {
+         if status.zone.water.is_some() && !status.grounded {
+            animator.set_row(anim_states.swim_row);
+        } else if status.grounded {
             if status.velocity.x.abs() > 20. {
                 animator.set_row(anim_states.run_row);
             } else {
@@ -449,8 +507,14 @@ fn actor_audio(actor_query: Query<(&ActorStatus, &ActorAudio)>, audio: Res<Audio
     for (status, actor_sounds) in &actor_query {
         if let Some(event) = &status.event {
             match event {
+                ActorEvent::Launched if status.surface == Surface::Bounce => {
+                    audio.play(actor_sounds.bounce.clone())
+                }
                 ActorEvent::Launched => audio.play(actor_sounds.jump.clone()),
-                ActorEvent::Landed => audio.play(actor_sounds.land.clone()),
+                ActorEvent::Landed => audio.play_with_settings(
+                    actor_sounds.land.clone(),
+                    PlaybackSettings::ONCE.with_speed(status.surface.land_pitch()),
+                ),
                 ActorEvent::Attack => audio.play(actor_sounds.attack.clone()),
                 ActorEvent::Hit => audio.play(actor_sounds.hit.clone()),
                 ActorEvent::Died => audio.play(actor_sounds.death.clone()),
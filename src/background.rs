@@ -0,0 +1,212 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::Level;
+use bevy_ecs_ldtk::prelude::*;
+use std::path::{Component as PathComponent, Path};
+
+use crate::camera::{camera_fit_inside_current_level, CameraRoom};
+use crate::GameState;
+
+pub struct BackgroundPlugin;
+
+/// Drawn behind every layer of the level
+pub const BACKGROUND_Z: f32 = -100.;
+
+/// Where LDtk file paths are relative to, inside the assets folder
+const LDTK_DIRECTORY: &str = "levels";
+
+/// An image behind the level that scrolls slower than the tiles, repeated to fill the view
+#[derive(Component, Clone, Debug)]
+pub struct ParallaxLayer {
+    pub level_iid: String,
+    /// 0 stays fixed to the camera, 1 scrolls with the level
+    pub factor: f32,
+    /// Middle of the level, where the layer lines up with the camera
+    pub anchor: Vec2,
+    pub image: Handle<Image>,
+}
+
+/// One copy of a parallax layer's image, offset by whole image widths
+#[derive(Component, Clone, Debug)]
+struct ParallaxTile {
+    index: i32,
+}
+
+impl ParallaxLayer {
+    /// Copies either side of the middle one, enough to cover a wide window
+    const TILES: i32 = 3;
+}
+
+/// Background images and scroll factors from the level's custom fields
+#[derive(Clone, Default, Debug)]
+pub struct LevelBackgrounds {
+    pub paths: Vec<String>,
+    pub factors: Vec<f32>,
+}
+
+impl LevelBackgrounds {
+    pub fn from_level(level: &Level) -> Self {
+        let mut backgrounds = LevelBackgrounds::default();
+
+        for field in level.field_instances.iter() {
+            match (field.identifier.as_str(), &field.value) {
+                // bevy_ecs_ldtk reads `Array<FilePath>` fields as strings
+                ("Backgrounds", FieldValue::Strings(paths) | FieldValue::FilePaths(paths)) => {
+                    backgrounds.paths = paths.iter().flatten().cloned().collect();
+                }
+                ("ParallaxFactors", FieldValue::Floats(factors)) => {
+                    backgrounds.factors = factors.iter().flatten().copied().collect();
+                }
+                (unknown, _) => println!("Unknown field \"{}\" on LDtk level!", unknown),
+            }
+        }
+
+        backgrounds
+    }
+
+    /// Each image with its factor, images without one stay fixed to the camera
+    pub fn layers(&self) -> impl Iterator<Item = (&str, f32)> {
+        self.paths.iter().enumerate().map(|(index, path)| {
+            (
+                path.as_str(),
+                self.factors.get(index).copied().unwrap_or_default(),
+            )
+        })
+    }
+}
+
+impl Plugin for BackgroundPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(spawn_backgrounds.after(camera_fit_inside_current_level))
+                .with_system(scroll_backgrounds.after(spawn_backgrounds)),
+        );
+    }
+}
+
+/// Turns a path relative to the LDtk project into one the asset server can load
+fn ldtk_asset_path(path: &str) -> String {
+    let mut parts: Vec<String> = Vec::new();
+
+    for part in Path::new(LDTK_DIRECTORY).join(path).components() {
+        match part {
+            PathComponent::ParentDir => {
+                parts.pop();
+            }
+            PathComponent::Normal(name) => parts.push(name.to_string_lossy().into_owned()),
+            _ => (),
+        }
+    }
+
+    parts.join("/")
+}
+
+/// Swaps the backgrounds over whenever the camera moves into a different level
+fn spawn_backgrounds(
+    mut commands: Commands,
+    camera_room: Res<CameraRoom>,
+    layer_query: Query<(Entity, &ParallaxLayer)>,
+    level_query: Query<(&Transform, &Handle<LdtkLevel>)>,
+    added_levels: Query<(), Added<Handle<LdtkLevel>>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    asset_server: Res<AssetServer>,
+) {
+    // Levels spawning again after a trip to the menu need their backgrounds back too
+    if !camera_room.is_changed() && added_levels.is_empty() {
+        return;
+    }
+
+    if layer_query
+        .iter()
+        .any(|(_, layer)| Some(&layer.level_iid) == camera_room.level_iid.as_ref())
+    {
+        return;
+    }
+
+    let Some((level_transform, level)) = level_query.iter().find_map(|(transform, handle)| {
+        ldtk_levels
+            .get(handle)
+            .filter(|ldtk_level| Some(&ldtk_level.level.iid) == camera_room.level_iid.as_ref())
+            .map(|ldtk_level| (transform, &ldtk_level.level))
+    }) else {
+        return;
+    };
+
+    for (entity, _) in &layer_query {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    let anchor = level_transform.translation.truncate()
+        + Vec2::new(level.px_wid as f32, level.px_hei as f32) / 2.;
+
+    for (depth, (path, factor)) in LevelBackgrounds::from_level(level).layers().enumerate() {
+        let image: Handle<Image> = asset_server.load(ldtk_asset_path(path));
+
+        commands
+            .spawn(SpatialBundle::from_transform(Transform::from_xyz(
+                anchor.x,
+                anchor.y,
+                BACKGROUND_Z + depth as f32,
+            )))
+            .insert(ParallaxLayer {
+                level_iid: level.iid.clone(),
+                factor,
+                anchor,
+                image: image.clone(),
+            })
+            .with_children(|layer| {
+                for index in -ParallaxLayer::TILES..=ParallaxLayer::TILES {
+                    layer
+                        .spawn(SpriteBundle {
+                            texture: image.clone(),
+                            ..Default::default()
+                        })
+                        .insert(ParallaxTile { index });
+                }
+            });
+    }
+}
+
+/// Follows the camera at each layer's own pace, wrapping sideways and keeping the image
+/// over the view vertically
+fn scroll_backgrounds(
+    camera_query: Query<
+        (&Transform, &OrthographicProjection),
+        (Without<ParallaxLayer>, Without<ParallaxTile>),
+    >,
+    mut layer_query: Query<(&ParallaxLayer, &mut Transform, &Children), Without<ParallaxTile>>,
+    mut tile_query: Query<(&ParallaxTile, &mut Transform), Without<ParallaxLayer>>,
+    images: Res<Assets<Image>>,
+) {
+    let Ok((camera_transform, projection)) = camera_query.get_single() else {
+        return;
+    };
+
+    let camera = camera_transform.translation.truncate();
+
+    for (layer, mut transform, children) in &mut layer_query {
+        let Some(size) = images.get(&layer.image).map(|image| image.size()) else {
+            continue;
+        };
+
+        let mut position = layer.anchor + (camera - layer.anchor) * (1. - layer.factor);
+
+        // Jump by whole images so there's always one under the middle of the view
+        position.x += ((camera.x - position.x) / size.x).round() * size.x;
+
+        let view_bottom = camera.y + projection.bottom;
+        let view_top = camera.y + projection.top;
+        let lowest = view_top - size.y / 2.;
+        let highest = view_bottom + size.y / 2.;
+        position.y = position.y.clamp(lowest.min(highest), lowest.max(highest));
+
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+
+        for child in children.iter() {
+            if let Ok((tile, mut tile_transform)) = tile_query.get_mut(*child) {
+                tile_transform.translation.x = tile.index as f32 * size.x;
+            }
+        }
+    }
+}
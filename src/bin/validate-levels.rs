@@ -0,0 +1,41 @@
+// Checks the LDtk project for broken levels without starting the game
+// Usage: cargo run --bin validate-levels [path/to/World.ldtk]
+
+use bevy_ecs_ldtk::ldtk::LdtkJson;
+use reaper::validation::validate_project;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| String::from("assets/levels/World.ldtk"));
+
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(error) => {
+            eprintln!("Couldn't read {}: {}", path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let project: LdtkJson = match serde_json::from_str(&contents) {
+        Ok(project) => project,
+        Err(error) => {
+            eprintln!("Couldn't parse {}: {}", path, error);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let errors = validate_project(&project);
+
+    if errors.is_empty() {
+        println!("{} levels in {} look good", project.levels.len(), path);
+        ExitCode::SUCCESS
+    } else {
+        for error in errors.iter() {
+            eprintln!("error: {}", error);
+        }
+        eprintln!("{} problems found in {}", errors.len(), path);
+        ExitCode::FAILURE
+    }
+}
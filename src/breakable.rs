@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_tilemap::tiles::TileVisible;
+use bevy_rapier2d::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+use crate::actor::Scythable;
+use crate::collision::merge_cells;
+use crate::world::{level_grid_size, Labeled};
+use crate::GameState;
+
+pub struct BreakablePlugin;
+
+/// A block in the level that the scythe can break, solid like a wall until then
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct Breakable;
+
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
+pub struct BreakableBundle {
+    breakable: Breakable,
+}
+
+/// A merged collider for a group of breakable blocks, knowing which tile each cell is
+#[derive(Component, Default, Debug)]
+pub struct BreakableWall {
+    pub cells: HashMap<GridCoords, Entity>,
+    /// Keeps one swing of the scythe from breaking the rebuilt colliders straight away
+    pub cooldown: f32,
+}
+
+impl BreakableWall {
+    const COOLDOWN: f32 = 0.3;
+}
+
+impl Plugin for BreakablePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(spawn_breakable_collision)
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(break_walls));
+    }
+}
+
+/// Merges breakable blocks into colliders like `spawn_wall_collision`, kept apart from the walls
+/// so breaking one only has to rebuild the blocks around it
+fn spawn_breakable_collision(
+    mut commands: Commands,
+    block_query: Query<(Entity, &GridCoords, &Parent), Added<Breakable>>,
+    parent_query: Query<&Parent, Without<Breakable>>,
+    level_query: Query<(Entity, &Handle<LdtkLevel>)>,
+    levels: Res<Assets<LdtkLevel>>,
+) {
+    let mut level_to_blocks: HashMap<Entity, HashMap<GridCoords, Entity>> = HashMap::new();
+
+    for (entity, &grid_coords, parent) in &block_query {
+        // Int grid cells are children of the layer, which is a child of the level
+        if let Ok(grandparent) = parent_query.get(parent.get()) {
+            level_to_blocks
+                .entry(grandparent.get())
+                .or_default()
+                .insert(grid_coords, entity);
+        }
+    }
+
+    for (level_entity, level_handle) in &level_query {
+        let Some(blocks) = level_to_blocks.remove(&level_entity) else {
+            continue;
+        };
+
+        let level = levels
+            .get(level_handle)
+            .expect("Level should be loaded by this point");
+
+        spawn_breakable_walls(
+            &mut commands,
+            level_entity,
+            blocks,
+            level_grid_size(level) as f32,
+            0.,
+        );
+    }
+}
+
+fn spawn_breakable_walls(
+    commands: &mut Commands,
+    level_entity: Entity,
+    blocks: HashMap<GridCoords, Entity>,
+    grid_size: f32,
+    cooldown: f32,
+) {
+    let cells: HashSet<GridCoords> = blocks.keys().copied().collect();
+
+    commands.entity(level_entity).with_children(|level| {
+        for rect in merge_cells(&cells) {
+            let wall = BreakableWall {
+                cells: rect
+                    .cells()
+                    .filter_map(|cell| blocks.get(&cell).map(|entity| (cell, *entity)))
+                    .collect(),
+                cooldown,
+            };
+
+            level
+                .spawn_empty()
+                .insert(Collider::cuboid(
+                    rect.width() as f32 * grid_size / 2.,
+                    rect.height() as f32 * grid_size / 2.,
+                ))
+                .insert(RigidBody::Fixed)
+                .insert(Friction::new(1.0))
+                .insert(Labeled {
+                    name: String::from("breakable"),
+                })
+                .insert(Scythable::default())
+                .insert(wall)
+                .insert(Transform::from_xyz(
+                    (rect.left + rect.right + 1) as f32 * grid_size / 2.,
+                    (rect.bottom + rect.top + 1) as f32 * grid_size / 2.,
+                    0.,
+                ))
+                .insert(GlobalTransform::default());
+        }
+    });
+}
+
+/// Breaks the block closest to whoever swung the scythe, and re-merges the rest of its collider
+fn break_walls(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut wall_query: Query<(Entity, &Parent, &mut BreakableWall, &mut Scythable)>,
+    level_query: Query<(&GlobalTransform, &Handle<LdtkLevel>)>,
+    levels: Res<Assets<LdtkLevel>>,
+) {
+    for (entity, parent, mut wall, mut scythable) in &mut wall_query {
+        if wall.cooldown > 0. {
+            wall.cooldown -= time.delta_seconds();
+            scythable.scythed = false;
+            continue;
+        }
+
+        if !scythable.scythed {
+            continue;
+        }
+
+        let Ok((level_transform, level_handle)) = level_query.get(parent.get()) else {
+            continue;
+        };
+
+        let Some(level) = levels.get(level_handle) else {
+            continue;
+        };
+
+        let grid_size = level_grid_size(level) as f32;
+        let hit_from = scythable
+            .hit_from
+            .map(|position| position - level_transform.translation().truncate())
+            .unwrap_or_default();
+
+        let Some(broken) = wall.cells.keys().copied().min_by(|a, b| {
+            let distance = |cell: &GridCoords| {
+                (Vec2::new(cell.x as f32 + 0.5, cell.y as f32 + 0.5) * grid_size)
+                    .distance_squared(hit_from)
+            };
+            distance(a).total_cmp(&distance(b))
+        }) else {
+            continue;
+        };
+
+        let mut blocks = std::mem::take(&mut wall.cells);
+
+        if let Some(tile) = blocks.remove(&broken) {
+            commands.entity(tile).insert(TileVisible(false));
+        }
+
+        commands.entity(entity).despawn_recursive();
+        spawn_breakable_walls(
+            &mut commands,
+            parent.get(),
+            blocks,
+            grid_size,
+            BreakableWall::COOLDOWN,
+        );
+    }
+}
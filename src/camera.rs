@@ -1,7 +1,7 @@
 use crate::player::Player;
 use crate::GameState;
 use bevy::prelude::*;
-use bevy_ecs_ldtk::LdtkLevel;
+use bevy_ecs_ldtk::{LdtkAsset, LdtkLevel, LevelSelection};
 
 pub struct CameraPlugin;
 
@@ -10,12 +10,25 @@ pub struct WindowInfo {
     aspect_ratio: f32,
 }
 
+/// The level the camera is currently kept inside, and how long is left of panning into it
+#[derive(Resource, Default, Debug)]
+pub struct CameraRoom {
+    pub level_iid: Option<String>,
+    pub pan_timer: f32,
+}
+
+impl CameraRoom {
+    const PAN_TIME: f32 = 0.6;
+    const PAN_SHARPNESS: f32 = 10.;
+}
+
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(ClearColor(Color::BLACK))
             .insert_resource(WindowInfo {
                 aspect_ratio: 16. / 9.,
             })
+            .init_resource::<CameraRoom>()
             .add_startup_system(spawn_camera)
             .add_system_set(
                 SystemSet::on_update(GameState::Playing)
@@ -29,7 +42,9 @@ impl Plugin for CameraPlugin {
 
 fn spawn_camera(mut commands: Commands) {
     //commands.spawn(PixelCameraBundle::from_resolution(VIEW_WIDTH, VIEW_HEIGHT));
-    let camera = Camera2dBundle::default();
+    let mut camera = Camera2dBundle::default();
+    // Reaches far enough back to draw the parallax backgrounds behind the level
+    camera.projection.far += -crate::background::BACKGROUND_Z;
     commands.spawn(camera);
 }
 
@@ -40,6 +55,7 @@ fn update_aspect_ratio(windows: ResMut<Windows>, mut window_info: ResMut<WindowI
 }
 
 pub fn camera_fit_inside_current_level(
+    time: Res<Time>,
     mut camera_query: Query<
         (
             &mut bevy::render::camera::OrthographicProjection,
@@ -47,24 +63,51 @@ pub fn camera_fit_inside_current_level(
         ),
         Without<Player>,
     >,
-    player_query: Query<&Transform, With<Player>>,
+    player_query: Query<(&Transform, ChangeTrackers<Player>), With<Player>>,
     level_query: Query<
         (&Transform, &Handle<LdtkLevel>),
         (Without<OrthographicProjection>, Without<Player>),
     >,
     ldtk_levels: Res<Assets<LdtkLevel>>,
+    level_selection: Res<LevelSelection>,
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
     window_info: Res<WindowInfo>,
+    mut camera_room: ResMut<CameraRoom>,
 ) {
-    if let Ok(Transform {
-        translation: player_translation,
-        ..
-    }) = player_query.get_single()
+    if let Ok((
+        Transform {
+            translation: player_translation,
+            ..
+        },
+        player_tracker,
+    )) = player_query.get_single()
     {
         let player_translation = *player_translation;
+        let selected_iid = ldtk_query
+            .get_single()
+            .ok()
+            .and_then(|handle| ldtk_assets.get(handle))
+            .and_then(|ldtk| crate::world::selected_level(&ldtk.project, &level_selection))
+            .map(|level| level.iid.clone());
         let (mut orthographic_projection, mut camera_transform) = camera_query.single_mut();
         for (level_transform, level_handle) in &level_query {
             if let Some(ldtk_level) = ldtk_levels.get(level_handle) {
                 let level = &ldtk_level.level;
+
+                // Neighbouring levels are loaded too, only the selected one bounds the camera
+                if selected_iid.as_ref() != Some(&level.iid) {
+                    continue;
+                }
+
+                if camera_room.level_iid.as_ref() != Some(&level.iid) {
+                    // Walking into the next room pans over, arriving through a door just cuts
+                    if camera_room.level_iid.is_some() && !player_tracker.is_added() {
+                        camera_room.pan_timer = CameraRoom::PAN_TIME;
+                    }
+                    camera_room.level_iid = Some(level.iid.clone());
+                }
+
                 let cam_height = 360.0;
                 let cam_width = cam_height * window_info.aspect_ratio;
                 orthographic_projection.scaling_mode = bevy::render::camera::ScalingMode::None;
@@ -73,8 +116,7 @@ pub fn camera_fit_inside_current_level(
                 orthographic_projection.right = cam_width / 2.;
                 orthographic_projection.left = cam_width / -2.;
 
-                camera_transform.translation.x = player_translation.x;
-                camera_transform.translation.y = player_translation.y;
+                let mut target = player_translation.truncate();
 
                 let level_height = level.px_hei as f32;
                 let level_bottom = level_transform.translation.y;
@@ -84,35 +126,44 @@ pub fn camera_fit_inside_current_level(
                 let level_left = level_transform.translation.x;
                 let level_right = level_left + level_width;
 
-                let camera_top = orthographic_projection.top + camera_transform.translation.y;
-                let camera_bottom = orthographic_projection.bottom + camera_transform.translation.y;
+                let camera_top = orthographic_projection.top + target.y;
+                let camera_bottom = orthographic_projection.bottom + target.y;
 
-                let camera_left = orthographic_projection.left + camera_transform.translation.x;
-                let camera_right = orthographic_projection.right + camera_transform.translation.x;
+                let camera_left = orthographic_projection.left + target.x;
+                let camera_right = orthographic_projection.right + target.x;
 
                 if level_height < cam_height {
-                    camera_transform.translation.y =
-                        level_transform.translation.y + (level_height / 2.);
+                    target.y = level_transform.translation.y + (level_height / 2.);
                 } else {
                     if camera_bottom < level_bottom {
-                        camera_transform.translation.y += level_bottom - camera_bottom;
+                        target.y += level_bottom - camera_bottom;
                     }
                     if camera_top > level_top {
-                        camera_transform.translation.y += level_top - camera_top;
+                        target.y += level_top - camera_top;
                     }
                 }
 
                 if level_width < cam_width {
-                    camera_transform.translation.x =
-                        level_transform.translation.x + (level_width / 2.);
+                    target.x = level_transform.translation.x + (level_width / 2.);
                 } else {
                     if camera_left < level_left {
-                        camera_transform.translation.x += level_left - camera_left;
+                        target.x += level_left - camera_left;
                     }
                     if camera_right > level_right {
-                        camera_transform.translation.x += level_right - camera_right;
+                        target.x += level_right - camera_right;
                     }
                 }
+
+                if camera_room.pan_timer > 0. {
+                    camera_room.pan_timer -= time.delta_seconds();
+
+                    let current = camera_transform.translation.truncate();
+                    let blend = 1. - (-CameraRoom::PAN_SHARPNESS * time.delta_seconds()).exp();
+                    target = current.lerp(target, blend);
+                }
+
+                camera_transform.translation.x = target.x;
+                camera_transform.translation.y = target.y;
             }
         }
     }
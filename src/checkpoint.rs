@@ -0,0 +1,204 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::actor::{ActorEvent, ActorStatus};
+use crate::loading::AudioAssets;
+use crate::player::Player;
+use crate::world::{ChangeLevelEvent, Labeled, ReloadWorldEvent};
+use crate::GameState;
+
+pub struct CheckpointPlugin;
+
+/// Sent when the player touches something deadly, the checkpoint decides what happens next
+pub struct PlayerDiedEvent;
+
+/// Where the player comes back after dying, cleared whenever the level restarts from scratch
+#[derive(Resource, Default, Debug)]
+pub struct ActiveCheckpoint {
+    pub iid: Option<String>,
+    pub position: Vec3,
+}
+
+#[derive(Component, Default, Clone, Debug)]
+pub struct Checkpoint {
+    pub iid: String,
+    pub active: bool,
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct CheckpointBundle {
+    #[bundle]
+    pub sprite_bundle: SpriteBundle,
+    pub collider: Collider,
+    pub sensor: Sensor,
+    pub label: Labeled,
+    pub checkpoint: Checkpoint,
+}
+
+impl Plugin for CheckpointPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveCheckpoint>()
+            .add_event::<PlayerDiedEvent>()
+            .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(clear_checkpoint))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(activate_checkpoints)
+                    .with_system(restore_checkpoint),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(respawn_at_checkpoint)
+                    .with_system(forget_checkpoint)
+                    .after(crate::player::player_death),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(checkpoint_visuals)
+                    .after(activate_checkpoints)
+                    .after(restore_checkpoint),
+            );
+    }
+}
+
+impl LdtkEntity for CheckpointBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        _layer_instance: &LayerInstance,
+        _tileset: Option<&Handle<Image>>,
+        _tileset_definition: Option<&TilesetDefinition>,
+        _asset_server: &AssetServer,
+        _texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Self {
+        for field in entity_instance.field_instances.iter() {
+            println!(
+                "Unknown field \"{}\" on LDtk checkpoint object!",
+                field.identifier
+            );
+        }
+
+        CheckpointBundle {
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite {
+                    color: Color::hex("5a6988").unwrap(),
+                    custom_size: Some(Vec2::new(4., 24.)),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(Vec3::new(0., 0., 0.5)),
+                ..Default::default()
+            },
+            collider: Collider::cuboid(8., 16.),
+            sensor: Sensor,
+            label: Labeled {
+                name: String::from("checkpoint"),
+            },
+            checkpoint: Checkpoint {
+                iid: entity_instance.iid.clone(),
+                active: false,
+            },
+        }
+    }
+}
+
+fn activate_checkpoints(
+    player_query: Query<&Transform, With<Player>>,
+    mut checkpoints: Query<(&mut Checkpoint, &GlobalTransform)>,
+    mut active_checkpoint: ResMut<ActiveCheckpoint>,
+    rapier_context: Res<RapierContext>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for player_transform in &player_query {
+        let shape = Collider::capsule_y(5.5, 5.5);
+        let shape_pos = player_transform.translation.truncate();
+
+        rapier_context.intersections_with_shape(
+            shape_pos,
+            0.,
+            &shape,
+            QueryFilter::new(),
+            |entity| -> bool {
+                if let Ok((checkpoint, transform)) = checkpoints.get(entity) {
+                    if !checkpoint.active {
+                        active_checkpoint.iid = Some(checkpoint.iid.clone());
+                        // The player is worldly, so the checkpoint's world position is where they go
+                        active_checkpoint.position = transform
+                            .translation()
+                            .truncate()
+                            .extend(player_transform.translation.z);
+                        audio.play(audio_assets.unlocked.clone());
+                    }
+                }
+                true
+            },
+        );
+    }
+
+    for (mut checkpoint, _) in &mut checkpoints {
+        let active = active_checkpoint.iid.as_ref() == Some(&checkpoint.iid);
+        if checkpoint.active != active {
+            checkpoint.active = active;
+        }
+    }
+}
+
+/// Checkpoints respawned with their level remember whether they were the active one
+fn restore_checkpoint(
+    mut checkpoints: Query<&mut Checkpoint, Added<Checkpoint>>,
+    active_checkpoint: Res<ActiveCheckpoint>,
+) {
+    for mut checkpoint in &mut checkpoints {
+        checkpoint.active = active_checkpoint.iid.as_ref() == Some(&checkpoint.iid);
+    }
+}
+
+fn respawn_at_checkpoint(
+    mut died_events: EventReader<PlayerDiedEvent>,
+    mut player_query: Query<(&mut Transform, &mut ActorStatus), With<Player>>,
+    active_checkpoint: Res<ActiveCheckpoint>,
+    mut reload_writer: EventWriter<ReloadWorldEvent>,
+) {
+    if died_events.iter().count() == 0 {
+        return;
+    }
+
+    if active_checkpoint.iid.is_none() {
+        reload_writer.send(ReloadWorldEvent);
+        return;
+    }
+
+    // Only the player goes back, souls, doors and everything else keep their progress
+    for (mut transform, mut status) in &mut player_query {
+        transform.translation = active_checkpoint.position;
+        status.velocity = Vec2::ZERO;
+        status.attack_direction = None;
+        status.event = Some(ActorEvent::Died);
+    }
+}
+
+/// Restarting or leaving the level starts it over from the beginning
+fn forget_checkpoint(
+    mut reload_events: EventReader<ReloadWorldEvent>,
+    mut change_events: EventReader<ChangeLevelEvent>,
+    mut active_checkpoint: ResMut<ActiveCheckpoint>,
+) {
+    let restarted = reload_events.iter().count() > 0 || change_events.iter().count() > 0;
+
+    if restarted && active_checkpoint.iid.is_some() {
+        *active_checkpoint = ActiveCheckpoint::default();
+    }
+}
+
+fn clear_checkpoint(mut active_checkpoint: ResMut<ActiveCheckpoint>) {
+    *active_checkpoint = ActiveCheckpoint::default();
+}
+
+fn checkpoint_visuals(mut checkpoints: Query<(&Checkpoint, &mut Sprite), Changed<Checkpoint>>) {
+    for (checkpoint, mut sprite) in &mut checkpoints {
+        sprite.color = if checkpoint.active {
+            Color::hex("63c74d").unwrap()
+        } else {
+            Color::hex("5a6988").unwrap()
+        };
+    }
+}
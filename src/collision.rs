@@ -0,0 +1,291 @@
+use bevy_ecs_ldtk::prelude::GridCoords;
+use std::collections::{HashMap, HashSet};
+
+/// A rectangle of grid cells, inclusive on every side
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub struct GridRect {
+    pub left: i32,
+    pub right: i32,
+    pub bottom: i32,
+    pub top: i32,
+}
+
+impl GridRect {
+    pub fn width(&self) -> i32 {
+        self.right - self.left + 1
+    }
+
+    pub fn height(&self) -> i32 {
+        self.top - self.bottom + 1
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = GridCoords> + '_ {
+        (self.bottom..=self.top)
+            .flat_map(move |y| (self.left..=self.right).map(move |x| GridCoords { x, y }))
+    }
+}
+
+/// Covers the cells with as few non-overlapping rectangles as we can cheaply find
+pub fn merge_cells(cells: &HashSet<GridCoords>) -> Vec<GridRect> {
+    let plates = plate_rects(cells);
+    let greedy = greedy_rects(cells);
+
+    // Greedy is nearly always better, but keep whichever came out smaller
+    if greedy.len() <= plates.len() {
+        greedy
+    } else {
+        plates
+    }
+}
+
+/// From the bevy_ecs_ldtk platformer example:
+/// 1. combine cells into flat "plates" in each individual row
+/// 2. combine identical plates into rectangles across multiple rows
+pub fn plate_rects(cells: &HashSet<GridCoords>) -> Vec<GridRect> {
+    /// A run of cells that is 1 tile tall
+    #[derive(Clone, Eq, PartialEq, Debug, Hash)]
+    struct Plate {
+        left: i32,
+        right: i32,
+    }
+
+    let Some((min, max)) = bounds(cells) else {
+        return Vec::new();
+    };
+
+    let mut plate_stack: Vec<Vec<Plate>> = Vec::new();
+
+    for y in min.y..=max.y {
+        let mut row_plates: Vec<Plate> = Vec::new();
+        let mut plate_start = None;
+
+        // + 1 to the width so the algorithm "terminates" plates that touch the right edge
+        for x in min.x..=max.x + 1 {
+            match (plate_start, cells.contains(&GridCoords { x, y })) {
+                (Some(s), false) => {
+                    row_plates.push(Plate {
+                        left: s,
+                        right: x - 1,
+                    });
+                    plate_start = None;
+                }
+                (None, true) => plate_start = Some(x),
+                _ => (),
+            }
+        }
+
+        plate_stack.push(row_plates);
+    }
+
+    let mut rect_builder: HashMap<Plate, GridRect> = HashMap::new();
+    let mut prev_row: Vec<Plate> = Vec::new();
+    let mut rects: Vec<GridRect> = Vec::new();
+
+    // an extra empty row so the algorithm "finishes" the rects that touch the top edge
+    plate_stack.push(Vec::new());
+
+    for (y, current_row) in (min.y..).zip(plate_stack) {
+        for prev_plate in &prev_row {
+            if !current_row.contains(prev_plate) {
+                // remove the finished rect so that the same plate in the future starts a new rect
+                if let Some(rect) = rect_builder.remove(prev_plate) {
+                    rects.push(rect);
+                }
+            }
+        }
+        for plate in &current_row {
+            rect_builder
+                .entry(plate.clone())
+                .and_modify(|e| e.top += 1)
+                .or_insert(GridRect {
+                    bottom: y,
+                    top: y,
+                    left: plate.left,
+                    right: plate.right,
+                });
+        }
+        prev_row = current_row;
+    }
+
+    rects
+}
+
+/// Grows each rectangle from the lowest uncovered cell, as far right and then as far up as it fits.
+/// Unlike plates, rows of different widths can still share a rectangle where they overlap.
+pub fn greedy_rects(cells: &HashSet<GridCoords>) -> Vec<GridRect> {
+    let Some((min, max)) = bounds(cells) else {
+        return Vec::new();
+    };
+
+    let mut covered: HashSet<GridCoords> = HashSet::new();
+    let mut rects = Vec::new();
+    let free = |covered: &HashSet<GridCoords>, x, y| {
+        let coords = GridCoords { x, y };
+        cells.contains(&coords) && !covered.contains(&coords)
+    };
+
+    for y in min.y..=max.y {
+        for x in min.x..=max.x {
+            if !free(&covered, x, y) {
+                continue;
+            }
+
+            let mut right = x;
+            while free(&covered, right + 1, y) {
+                right += 1;
+            }
+
+            let mut top = y;
+            while (x..=right).all(|column| free(&covered, column, top + 1)) {
+                top += 1;
+            }
+
+            let rect = GridRect {
+                left: x,
+                right,
+                bottom: y,
+                top,
+            };
+            covered.extend(rect.cells());
+            rects.push(rect);
+        }
+    }
+
+    rects
+}
+
+fn bounds(cells: &HashSet<GridCoords>) -> Option<(GridCoords, GridCoords)> {
+    let first = *cells.iter().next()?;
+
+    Some(cells.iter().fold((first, first), |(min, max), cell| {
+        (
+            GridCoords {
+                x: min.x.min(cell.x),
+                y: min.y.min(cell.y),
+            },
+            GridCoords {
+                x: max.x.max(cell.x),
+                y: max.y.max(cell.y),
+            },
+        )
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Small xorshift generator, so the random grids are the same on every run
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> i32 {
+            (self.next() % n) as i32
+        }
+    }
+
+    /// Random grids from sparse to nearly full, including negative coordinates
+    fn random_grids() -> impl Iterator<Item = HashSet<GridCoords>> {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+        (0..500).map(move |_| {
+            let width = rng.below(12) + 1;
+            let height = rng.below(12) + 1;
+            let fill = rng.below(100);
+            let offset = GridCoords {
+                x: rng.below(20) - 10,
+                y: rng.below(20) - 10,
+            };
+
+            let mut cells = HashSet::new();
+            for y in 0..height {
+                for x in 0..width {
+                    if rng.below(100) < fill {
+                        cells.insert(GridCoords {
+                            x: x + offset.x,
+                            y: y + offset.y,
+                        });
+                    }
+                }
+            }
+            cells
+        })
+    }
+
+    /// Every cell is covered exactly once, and nothing outside the input is
+    fn assert_exact_cover(cells: &HashSet<GridCoords>, rects: &[GridRect]) {
+        let mut covered = HashSet::new();
+
+        for rect in rects {
+            assert!(
+                rect.width() > 0 && rect.height() > 0,
+                "empty rect {:?}",
+                rect
+            );
+
+            for cell in rect.cells() {
+                assert!(cells.contains(&cell), "{:?} covers {:?}", rect, cell);
+                assert!(covered.insert(cell), "{:?} overlaps at {:?}", rect, cell);
+            }
+        }
+
+        assert_eq!(covered, *cells);
+    }
+
+    #[test]
+    fn plates_cover_cells_exactly() {
+        for cells in random_grids() {
+            assert_exact_cover(&cells, &plate_rects(&cells));
+        }
+    }
+
+    #[test]
+    fn greedy_covers_cells_exactly() {
+        for cells in random_grids() {
+            assert_exact_cover(&cells, &greedy_rects(&cells));
+        }
+    }
+
+    #[test]
+    fn merged_is_never_worse_than_plates() {
+        let mut plate_total = 0;
+        let mut merged_total = 0;
+
+        for cells in random_grids() {
+            let merged = merge_cells(&cells);
+            assert_exact_cover(&cells, &merged);
+
+            let plates = plate_rects(&cells).len();
+            assert!(merged.len() <= plates);
+
+            plate_total += plates;
+            merged_total += merged.len();
+        }
+
+        assert!(merged_total < plate_total);
+    }
+
+    #[test]
+    fn empty_input_has_no_rects() {
+        assert!(merge_cells(&HashSet::new()).is_empty());
+    }
+
+    #[test]
+    fn bump_merges_better_than_plates() {
+        // A 2 wide column with a bump on one side, plates split it into three rows
+        let cells: HashSet<GridCoords> = [(0, 0), (1, 0), (0, 1), (1, 1), (2, 1), (0, 2), (1, 2)]
+            .into_iter()
+            .map(|(x, y)| GridCoords { x, y })
+            .collect();
+
+        assert_eq!(plate_rects(&cells).len(), 3);
+        assert_eq!(merge_cells(&cells).len(), 2);
+    }
+}
@@ -0,0 +1,148 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::actor::ActorStatus;
+use crate::gate::KeyRing;
+use crate::player::Player;
+use crate::soul::SoulWallet;
+use crate::GameState;
+
+pub struct DesignerPlugin;
+
+/// While enabled, saving the LDtk project reloads the level around the player instead of resetting them
+#[derive(Resource, Debug)]
+pub struct DesignerMode {
+    pub enabled: bool,
+}
+
+impl Default for DesignerMode {
+    fn default() -> Self {
+        // Hot reloading only watches for changes in debug builds anyway
+        DesignerMode {
+            enabled: cfg!(debug_assertions),
+        }
+    }
+}
+
+/// The player as they were when the project changed on disk, waiting for the level to respawn
+#[derive(Resource, Default, Debug)]
+pub struct PendingPlayerState {
+    pub snapshot: Option<PlayerSnapshot>,
+    pub timeout: f32,
+}
+
+#[derive(Clone, Debug)]
+pub struct PlayerSnapshot {
+    pub position: Vec3,
+    pub velocity: Vec2,
+    pub facing_left: bool,
+    pub wallet: SoulWallet,
+    pub key_ring: KeyRing,
+}
+
+impl PendingPlayerState {
+    /// How long to wait for the respawned player before giving up on restoring them
+    const TIMEOUT: f32 = 2.;
+}
+
+impl Plugin for DesignerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DesignerMode>()
+            .init_resource::<PendingPlayerState>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(snapshot_player_on_change)
+                    .with_system(restore_player_after_reload)
+                    .before(crate::streaming::record_room_entry),
+            );
+
+        #[cfg(debug_assertions)]
+        {
+            app.add_system(toggle_designer_mode);
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+fn toggle_designer_mode(input: Res<Input<KeyCode>>, mut designer_mode: ResMut<DesignerMode>) {
+    if input.just_pressed(KeyCode::F1) {
+        designer_mode.enabled = !designer_mode.enabled;
+        println!(
+            "designer mode {}",
+            if designer_mode.enabled { "on" } else { "off" }
+        );
+    }
+}
+
+fn snapshot_player_on_change(
+    mut project_events: EventReader<AssetEvent<LdtkAsset>>,
+    mut level_events: EventReader<AssetEvent<LdtkLevel>>,
+    designer_mode: Res<DesignerMode>,
+    player_query: Query<(&Transform, &ActorStatus, &SoulWallet, &KeyRing), With<Player>>,
+    mut pending: ResMut<PendingPlayerState>,
+) {
+    let project_changed = project_events
+        .iter()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }));
+    let level_changed = level_events
+        .iter()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }));
+
+    if !designer_mode.enabled || !(project_changed || level_changed) {
+        return;
+    }
+
+    // A level and the project can both change in one save, keep the state from before either
+    if pending.snapshot.is_some() {
+        return;
+    }
+
+    if let Ok((transform, status, wallet, key_ring)) = player_query.get_single() {
+        println!("levels changed on disk, keeping the player where they are");
+        pending.snapshot = Some(PlayerSnapshot {
+            position: transform.translation,
+            velocity: status.velocity,
+            facing_left: status.facing_left,
+            wallet: wallet.clone(),
+            key_ring: key_ring.clone(),
+        });
+        pending.timeout = PendingPlayerState::TIMEOUT;
+    }
+}
+
+fn restore_player_after_reload(
+    time: Res<Time>,
+    mut pending: ResMut<PendingPlayerState>,
+    mut player_query: Query<
+        (
+            &mut Transform,
+            &mut ActorStatus,
+            &mut SoulWallet,
+            &mut KeyRing,
+        ),
+        Added<Player>,
+    >,
+) {
+    let Some(snapshot) = pending.snapshot.clone() else {
+        return;
+    };
+
+    let mut restored = false;
+
+    // Neighbouring levels may respawn players as well, only one of them is kept afterwards
+    for (mut transform, mut status, mut wallet, mut key_ring) in &mut player_query {
+        transform.translation = snapshot.position;
+        status.velocity = snapshot.velocity;
+        status.facing_left = snapshot.facing_left;
+        *wallet = snapshot.wallet.clone();
+        *key_ring = snapshot.key_ring.clone();
+        restored = true;
+    }
+
+    pending.timeout -= time.delta_seconds();
+
+    // The player might have survived the reload untouched, in which case there is nothing to do
+    if restored || pending.timeout <= 0. {
+        pending.snapshot = None;
+    }
+}
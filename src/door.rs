@@ -1,16 +1,137 @@
 use crate::loading::AudioAssets;
 use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::{LdtkJson, Level};
 use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::*;
 
-use crate::{soul::CollectedSoulEvent, sprite_anim::SpriteAnimator, world::Labeled, GameState};
+use crate::{
+    player::Player, signal::SignalReceiver, soul::SoulWallet, sprite_anim::SpriteAnimator,
+    world::Labeled, GameState,
+};
 
 pub struct DoorPlugin;
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+#[derive(Clone, Eq, PartialEq, Debug, Default, Component)]
 pub struct Door {
-    pub next_level: usize,
+    /// Lets other doors use this one as their entry point
+    pub name: Option<String>,
+    /// LDtk identifier or IID of the level this door leads to
+    pub target_level: Option<String>,
+    /// Name of the door the player arrives at in the target level
+    pub entry_door: Option<String>,
+    pub win_game: bool,
     pub required_souls: usize,
+    pub mode: DoorMode,
+    /// Only souls from this group count toward opening the door
+    pub soul_group: Option<String>,
+    /// Grain the player can pay to open the door instead
+    pub grain_price: Option<usize>,
+    pub open: bool,
+    /// Opened by a switch rather than souls or grain, so switching it off closes it again
+    pub opened_by_signal: bool,
+    /// Cleared while the player stands in the door they arrived through
+    pub disarmed: bool,
+}
+
+/// How a door uses the souls in the player's wallet
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Default)]
+pub enum DoorMode {
+    /// Opens once the player is carrying enough souls
+    #[default]
+    Threshold,
+    /// Takes the souls out of the player's wallet when they walk in to it
+    Consume,
+}
+
+pub struct DoorOpenedEvent {
+    pub door_entity: Entity,
+}
+
+pub struct DoorClosedEvent {
+    pub door_entity: Entity,
+}
+
+/// Shows the grain a door can be bought with, above the souls it needs
+#[derive(Component)]
+struct GrainPriceText;
+
+impl Door {
+    pub fn from_entity_instance(entity_instance: &EntityInstance) -> Door {
+        let mut door = Door::default();
+
+        for field in entity_instance.field_instances.iter() {
+            match field.identifier.as_str() {
+                "Name" => {
+                    if let FieldValue::String(value) = &field.value {
+                        door.name = value.clone().filter(|name| !name.is_empty());
+                    }
+                }
+                "TargetLevel" => {
+                    if let FieldValue::String(value) = &field.value {
+                        door.target_level = value.clone().filter(|level| !level.is_empty());
+                    }
+                }
+                "EntryDoor" => {
+                    if let FieldValue::String(value) = &field.value {
+                        door.entry_door = value.clone().filter(|name| !name.is_empty());
+                    }
+                }
+                "WinGame" => {
+                    if let FieldValue::Bool(value) = field.value {
+                        door.win_game = value;
+                    }
+                }
+                "SoulsNeeded" => {
+                    if let FieldValue::Int(Some(value)) = field.value {
+                        door.required_souls = value as usize;
+                    }
+                }
+                "Mode" => {
+                    if let FieldValue::String(Some(value)) = &field.value {
+                        match value.to_lowercase().as_str() {
+                            "threshold" => door.mode = DoorMode::Threshold,
+                            "consume" => door.mode = DoorMode::Consume,
+                            unknown => {
+                                println!("Unknown door mode \"{}\" on LDtk door object!", unknown)
+                            }
+                        }
+                    }
+                }
+                "SoulGroup" => {
+                    if let FieldValue::String(value) = &field.value {
+                        door.soul_group = value.clone().filter(|group| !group.is_empty());
+                    }
+                }
+                "GrainPrice" => {
+                    if let FieldValue::Int(value) = field.value {
+                        door.grain_price = value.map(|price| price.max(0) as usize);
+                    }
+                }
+                "StartActive" | "ResetTime" => (),
+                unknown => println!("Unknown field \"{}\" on LDtk door object!", unknown),
+            }
+        }
+
+        door.open = door.required_souls == 0;
+
+        door
+    }
+
+    /// How many more souls the player needs before this door will open
+    pub fn souls_remaining(&self, wallet: &SoulWallet) -> usize {
+        if self.open {
+            0
+        } else {
+            self.required_souls
+                .saturating_sub(wallet.count(self.soul_group.as_deref()))
+        }
+    }
+}
+
+/// The named door the player should appear at next time a level spawns
+#[derive(Resource, Default, Debug)]
+pub struct LevelEntry {
+    pub door: Option<String>,
 }
 
 #[derive(Clone, Default, Bundle)]
@@ -23,16 +144,40 @@ pub struct DoorBundle {
     pub sensor: Sensor,
     pub active_events: ActiveEvents,
     pub door: Door,
+    pub receiver: SignalReceiver,
 }
 
 impl Plugin for DoorPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(
-            SystemSet::on_update(GameState::Playing).with_system(add_souls_needed_text),
-        )
-        .add_system_set(
-            SystemSet::on_update(GameState::Playing).with_system(update_souls_needed_text),
-        );
+        app.init_resource::<LevelEntry>()
+            .add_event::<DoorOpenedEvent>()
+            .add_event::<DoorClosedEvent>()
+            .add_system(validate_door_links)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(place_player_at_entry),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(add_souls_needed_text),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(unlock_threshold_doors)
+                    .with_system(update_souls_needed_text)
+                    .after(crate::soul::collect_souls),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(signal_doors)
+                    .after(crate::signal::receive_signals),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(open_doors)
+                    .with_system(close_doors)
+                    .after(unlock_threshold_doors)
+                    .after(signal_doors)
+                    .after(crate::player::player_win),
+            );
     }
 }
 
@@ -45,25 +190,13 @@ impl LdtkEntity for DoorBundle {
         asset_server: &AssetServer,
         texture_atlases: &mut Assets<TextureAtlas>,
     ) -> Self {
-        let mut door = Door::default();
+        let door = Door::from_entity_instance(entity_instance);
 
-        for field in entity_instance.field_instances.iter() {
-            match field.identifier.as_str() {
-                "NextLevel" => {
-                    if let FieldValue::Int(Some(value)) = field.value {
-                        door.next_level = value as usize;
-                    }
-                }
-                "SoulsNeeded" => {
-                    if let FieldValue::Int(Some(value)) = field.value {
-                        door.required_souls = value as usize;
-                    }
-                }
-                unknown => println!("Unknown field \"{}\" on LDtk door object!", unknown),
-            }
-        }
-
-        let texture_handle = asset_server.load("sprites/door_closed.png");
+        let texture_handle = if door.open {
+            asset_server.load("sprites/door_open.png")
+        } else {
+            asset_server.load("sprites/door_closed.png")
+        };
         let texture_atlas =
             TextureAtlas::from_grid(texture_handle, Vec2::new(16., 32.), 4, 1, None, None);
         let texture_atlas_handle = texture_atlases.add(texture_atlas);
@@ -77,11 +210,16 @@ impl LdtkEntity for DoorBundle {
             sprite_animator: SpriteAnimator::new(0, 3, 4, 0.2, true, true),
             collider: Collider::cuboid(8., 16.),
             label: Labeled {
-                name: String::from("door to ") + door.next_level.to_string().as_str(),
+                name: if door.win_game {
+                    String::from("door to victory")
+                } else {
+                    String::from("door to ") + door.target_level.as_deref().unwrap_or("nowhere")
+                },
             },
             sensor: Sensor,
             active_events: ActiveEvents::COLLISION_EVENTS,
             door,
+            receiver: SignalReceiver::from_entity_instance(entity_instance, false),
         }
     }
 }
@@ -107,43 +245,284 @@ fn add_souls_needed_text(
                 ..Default::default()
             })
             .set_parent(entity);
+
+        if let Some(price) = door.grain_price.filter(|_| !door.open) {
+            commands
+                .spawn(Text2dBundle {
+                    text: Text::from_section(
+                        format!("Down: {}g", price),
+                        TextStyle {
+                            font_size: 8.,
+                            color: crate::harvest::GRAIN_COLOR,
+                            ..text_style.clone()
+                        },
+                    )
+                    .with_alignment(TextAlignment::CENTER),
+                    transform: Transform::from_xyz(0., 40., -1.),
+                    ..Default::default()
+                })
+                .insert(GrainPriceText)
+                .set_parent(entity);
+        }
+    }
+}
+
+fn unlock_threshold_doors(
+    player_query: Query<&SoulWallet, (With<Player>, Changed<SoulWallet>)>,
+    mut doors: Query<(Entity, &mut Door)>,
+    mut door_opened_writer: EventWriter<DoorOpenedEvent>,
+) {
+    for wallet in &player_query {
+        for (entity, mut door) in &mut doors {
+            if door.mode == DoorMode::Threshold && !door.open && door.souls_remaining(wallet) == 0
+            {
+                door.open = true;
+                door_opened_writer.send(DoorOpenedEvent {
+                    door_entity: entity,
+                });
+            }
+        }
+    }
+}
+
+/// Doors linked to a switch open when it turns on, whatever souls they ask for, and close
+/// again when it turns off or resets
+fn signal_doors(
+    mut doors: Query<(Entity, &mut Door, &SignalReceiver), Changed<SignalReceiver>>,
+    mut door_opened_writer: EventWriter<DoorOpenedEvent>,
+    mut door_closed_writer: EventWriter<DoorClosedEvent>,
+) {
+    for (entity, mut door, receiver) in &mut doors {
+        if receiver.active && !door.open {
+            door.open = true;
+            door.opened_by_signal = true;
+            door_opened_writer.send(DoorOpenedEvent {
+                door_entity: entity,
+            });
+        } else if !receiver.active && door.opened_by_signal {
+            door.open = false;
+            door.opened_by_signal = false;
+            door_closed_writer.send(DoorClosedEvent {
+                door_entity: entity,
+            });
+        }
     }
 }
 
 fn update_souls_needed_text(
-    mut soul_events: EventReader<CollectedSoulEvent>,
-    mut text: Query<(&Parent, &mut Text)>,
-    mut doors: Query<(&mut Door, &mut Handle<TextureAtlas>)>,
+    player_query: Query<&SoulWallet, With<Player>>,
+    mut text: Query<(&Parent, &mut Text), Without<GrainPriceText>>,
+    mut price_text: Query<(&Parent, &mut Visibility), With<GrainPriceText>>,
+    doors: Query<&Door>,
+) {
+    for (parent, mut visibility) in &mut price_text {
+        if let Ok(door) = doors.get(parent.get()) {
+            if visibility.is_visible == door.open {
+                visibility.is_visible = !door.open;
+            }
+        }
+    }
+
+    if let Ok(wallet) = player_query.get_single() {
+        for (parent, mut text) in text.iter_mut() {
+            if let Ok(door) = doors.get(parent.get()) {
+                let remaining = match door.mode {
+                    DoorMode::Threshold => door.souls_remaining(wallet),
+                    DoorMode::Consume if door.open => 0,
+                    DoorMode::Consume => door.required_souls,
+                }
+                .to_string();
+
+                if text.sections[0].value != remaining {
+                    text.sections[0].value = remaining;
+                }
+            }
+        }
+    }
+}
+
+fn open_doors(
+    mut door_opened_events: EventReader<DoorOpenedEvent>,
+    mut doors: Query<&mut Handle<TextureAtlas>, With<Door>>,
     asset_server: Res<AssetServer>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
     audio: Res<Audio>,
     audio_assets: Res<AudioAssets>,
 ) {
-    for _ in soul_events.iter() {
-        for (parent, mut text) in text.iter_mut() {
-            if let Ok((mut door, mut image_handle)) = doors.get_mut(parent.get()) {
-                if door.required_souls > 0 {
-                    // Should do nothing if already open
-                    door.required_souls -= 1; // Apply new value for each soul event
-                    text.sections[0].value = door.required_souls.to_string();
-
-                    if door.required_souls == 0 {
-                        let texture_handle = asset_server.load("sprites/door_open.png");
-                        let texture_atlas = TextureAtlas::from_grid(
-                            texture_handle,
-                            Vec2::new(16., 32.),
-                            4,
-                            1,
-                            None,
-                            None,
-                        );
-                        let texture_atlas_handle = texture_atlases.add(texture_atlas);
-                        *image_handle = texture_atlas_handle.clone();
-
-                        audio.play(audio_assets.unlocked.clone());
-                    }
+    for ev in door_opened_events.iter() {
+        if let Ok(mut image_handle) = doors.get_mut(ev.door_entity) {
+            let texture_handle = asset_server.load("sprites/door_open.png");
+            let texture_atlas =
+                TextureAtlas::from_grid(texture_handle, Vec2::new(16., 32.), 4, 1, None, None);
+            let texture_atlas_handle = texture_atlases.add(texture_atlas);
+            *image_handle = texture_atlas_handle.clone();
+
+            audio.play(audio_assets.unlocked.clone());
+        }
+    }
+}
+
+fn close_doors(
+    mut door_closed_events: EventReader<DoorClosedEvent>,
+    mut doors: Query<&mut Handle<TextureAtlas>, With<Door>>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    for ev in door_closed_events.iter() {
+        if let Ok(mut image_handle) = doors.get_mut(ev.door_entity) {
+            let texture_handle = asset_server.load("sprites/door_closed.png");
+            let texture_atlas =
+                TextureAtlas::from_grid(texture_handle, Vec2::new(16., 32.), 4, 1, None, None);
+            *image_handle = texture_atlases.add(texture_atlas);
+        }
+    }
+}
+
+/// Every door placed in the level, read straight from the project
+pub fn level_doors(level: &Level) -> Vec<Door> {
+    level
+        .layer_instances
+        .iter()
+        .flatten()
+        .flat_map(|layer| layer.entity_instances.iter())
+        .filter(|entity_instance| entity_instance.identifier == "Door")
+        .map(Door::from_entity_instance)
+        .collect()
+}
+
+/// Lists every door whose target level or entry door can't be found in the project
+pub fn door_link_errors(project: &LdtkJson) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    for level in project.levels.iter() {
+        for door in level_doors(level) {
+            if door.win_game {
+                continue;
+            }
+
+            let Some(target) = &door.target_level else {
+                errors.push(format!(
+                    "Door in {} has no target level and does not win the game",
+                    level.identifier
+                ));
+                continue;
+            };
+
+            let Some(target_level) = crate::world::find_level(project, target) else {
+                errors.push(format!(
+                    "Door in {} leads to level \"{}\" which does not exist",
+                    level.identifier, target
+                ));
+                continue;
+            };
+
+            if let Some(entry) = &door.entry_door {
+                let entry_exists = level_doors(target_level)
+                    .iter()
+                    .any(|target_door| target_door.name.as_ref() == Some(entry));
+
+                if !entry_exists {
+                    errors.push(format!(
+                        "Door in {} enters {} through door \"{}\" which does not exist",
+                        level.identifier, target_level.identifier, entry
+                    ));
+                }
+            }
+        }
+    }
+
+    errors
+}
+
+pub fn validate_door_links(
+    mut asset_events: EventReader<AssetEvent<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    designer_mode: Res<crate::designer::DesignerMode>,
+) {
+    for event in asset_events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = event {
+            if let Some(ldtk) = ldtk_assets.get(handle) {
+                let errors = door_link_errors(&ldtk.project);
+
+                if errors.is_empty() {
+                    continue;
+                }
+
+                // Half-finished edits shouldn't crash the game while designing levels
+                if designer_mode.enabled && matches!(event, AssetEvent::Modified { .. }) {
+                    println!("Broken door links in LDtk project:\n{}", errors.join("\n"));
+                } else {
+                    panic!("Broken door links in LDtk project:\n{}", errors.join("\n"));
                 }
             }
         }
     }
 }
+
+pub fn place_player_at_entry(
+    mut level_entry: ResMut<LevelEntry>,
+    level_selection: Res<LevelSelection>,
+    mut player_query: Query<&mut Transform, Added<crate::player::Player>>,
+    mut doors: Query<(Entity, &mut Door)>,
+    transforms: Query<(&Transform, Option<&Parent>), Without<crate::player::Player>>,
+    levels: Query<&Handle<LdtkLevel>>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+) {
+    let selected_iid = ldtk_query
+        .get_single()
+        .ok()
+        .and_then(|handle| ldtk_assets.get(handle))
+        .and_then(|ldtk| crate::world::selected_level(&ldtk.project, &level_selection))
+        .map(|level| level.iid.clone());
+
+    if player_query.is_empty() {
+        return;
+    }
+
+    // Fresh players only get one chance at the entry, after that reloads use the room entry
+    let Some(entry_name) = level_entry.door.take() else {
+        return;
+    };
+
+    // Neighbouring levels spawn their own players too, move them all and let one be kept
+    for mut player_transform in &mut player_query {
+        for (entity, mut door) in &mut doors {
+            if door.name.as_ref() != Some(&entry_name) {
+                continue;
+            }
+
+            // Add up the door's parents by hand, their global transforms may not be ready yet
+            let mut translation = Vec3::ZERO;
+            let mut in_selected_level = false;
+            let mut current = Some(entity);
+
+            while let Some(ancestor) = current {
+                let Ok((transform, parent)) = transforms.get(ancestor) else {
+                    break;
+                };
+
+                translation += transform.translation;
+
+                let ldtk_level = levels
+                    .get(ancestor)
+                    .ok()
+                    .and_then(|handle| ldtk_levels.get(handle));
+                if let Some(ldtk_level) = ldtk_level {
+                    in_selected_level = selected_iid.as_ref() == Some(&ldtk_level.level.iid);
+                }
+
+                current = parent.map(|parent| parent.get());
+            }
+
+            // Neighbouring levels are loaded too, and may reuse the same door names
+            if in_selected_level {
+                player_transform.translation.x = translation.x;
+                player_transform.translation.y = translation.y;
+
+                door.disarmed = true;
+            }
+        }
+    }
+}
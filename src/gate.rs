@@ -0,0 +1,199 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::loading::AudioAssets;
+use crate::pickup::CollectedKeyEvent;
+use crate::player::Player;
+use crate::signal::SignalReceiver;
+use crate::world::Labeled;
+use crate::GameState;
+
+pub struct GatePlugin;
+
+/// Keys the player is currently carrying, lost when the level reloads
+#[derive(Component, Default, Clone, Debug)]
+pub struct KeyRing {
+    pub keys: Vec<String>,
+}
+
+impl KeyRing {
+    pub fn use_key(&mut self, id: &str) -> bool {
+        if let Some(index) = self.keys.iter().position(|key| key == id) {
+            self.keys.remove(index);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// A wall that opens when the player touches it carrying the matching key
+#[derive(Component, Default, Clone, Debug)]
+pub struct Gate {
+    pub key: Option<String>,
+    pub open: bool,
+    pub half_extents: Vec2,
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct GateBundle {
+    #[bundle]
+    pub sprite_bundle: SpriteBundle,
+    pub collider: Collider,
+    pub rigidbody: RigidBody,
+    pub label: Labeled,
+    pub gate: Gate,
+    pub receiver: SignalReceiver,
+}
+
+impl Plugin for GatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(collect_keys)
+                .with_system(unlock_gates)
+                .after(crate::pickup::check_for_pickups),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(signal_gates)
+                .after(crate::signal::receive_signals),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(sync_gate_colliders)
+                .after(unlock_gates)
+                .after(signal_gates),
+        );
+    }
+}
+
+/// Tint used for keys and gates, so designers can colour code them by id
+pub fn key_color(id: &str) -> Color {
+    match id {
+        "red" => Color::hex("e43b44").unwrap(),
+        "blue" => Color::hex("0099db").unwrap(),
+        "green" => Color::hex("63c74d").unwrap(),
+        "purple" => Color::hex("b55088").unwrap(),
+        _ => Color::hex("d9b866").unwrap(),
+    }
+}
+
+impl LdtkEntity for GateBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        _layer_instance: &LayerInstance,
+        _tileset: Option<&Handle<Image>>,
+        _tileset_definition: Option<&TilesetDefinition>,
+        _asset_server: &AssetServer,
+        _texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Self {
+        let mut gate = Gate {
+            key: None,
+            open: false,
+            half_extents: Vec2::new(
+                entity_instance.width as f32 / 2.,
+                entity_instance.height as f32 / 2.,
+            ),
+        };
+
+        for field in entity_instance.field_instances.iter() {
+            match field.identifier.as_str() {
+                "KeyId" => {
+                    if let FieldValue::String(Some(value)) = &field.value {
+                        gate.key = Some(value.to_lowercase());
+                    }
+                }
+                "StartActive" | "ResetTime" => (),
+                unknown => println!("Unknown field \"{}\" on LDtk gate object!", unknown),
+            }
+        }
+
+        let color = key_color(gate.key.as_deref().unwrap_or_default());
+
+        GateBundle {
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(gate.half_extents * 2.),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(Vec3::new(0., 0., 0.5)),
+                ..Default::default()
+            },
+            collider: Collider::cuboid(gate.half_extents.x, gate.half_extents.y),
+            rigidbody: RigidBody::Fixed,
+            label: Labeled {
+                name: String::from("gate"),
+            },
+            gate,
+            receiver: SignalReceiver::from_entity_instance(entity_instance, false),
+        }
+    }
+}
+
+fn collect_keys(
+    mut key_events: EventReader<CollectedKeyEvent>,
+    mut key_rings: Query<&mut KeyRing>,
+) {
+    for ev in key_events.iter() {
+        if let Ok(mut key_ring) = key_rings.get_mut(ev.collector_entity) {
+            key_ring.keys.push(ev.id.clone());
+        }
+    }
+}
+
+fn unlock_gates(
+    mut player_query: Query<(&GlobalTransform, &mut KeyRing), With<Player>>,
+    mut gates: Query<&mut Gate>,
+    rapier_context: Res<RapierContext>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for (transform, mut key_ring) in &mut player_query {
+        // A little bigger than the player, since the character controller stops them short of walls
+        let shape = Collider::capsule_y(6.5, 6.5);
+        let filter = QueryFilter::new().exclude_sensors();
+        let shape_pos = transform.translation().truncate();
+
+        rapier_context.intersections_with_shape(shape_pos, 0., &shape, filter, |entity| -> bool {
+            if let Ok(mut gate) = gates.get_mut(entity) {
+                if let Some(key) = gate.key.clone() {
+                    if !gate.open && key_ring.use_key(&key) {
+                        gate.open = true;
+                        audio.play(audio_assets.unlocked.clone());
+                    }
+                }
+            }
+            true
+        });
+    }
+}
+
+/// Linked gates follow their switch, opening while it is on
+fn signal_gates(mut gates: Query<(&mut Gate, &SignalReceiver), Changed<SignalReceiver>>) {
+    for (mut gate, receiver) in &mut gates {
+        if gate.open != receiver.active {
+            gate.open = receiver.active;
+        }
+    }
+}
+
+fn sync_gate_colliders(
+    mut gates: Query<(Entity, &Gate, &mut Visibility), Changed<Gate>>,
+    mut commands: Commands,
+) {
+    for (entity, gate, mut visibility) in &mut gates {
+        visibility.is_visible = !gate.open;
+
+        if gate.open {
+            commands.entity(entity).remove::<Collider>();
+        } else {
+            commands.entity(entity).insert(Collider::cuboid(
+                gate.half_extents.x,
+                gate.half_extents.y,
+            ));
+        }
+    }
+}
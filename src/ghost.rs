@@ -1,7 +1,9 @@
 use crate::actor::*;
 use crate::player::TouchDeath;
+use crate::soul::{SoulDecay, SoulFate, SoulTier};
 use crate::sprite_anim::SpriteAnimator;
-use crate::world::Labeled;
+use crate::world::{Labeled, Surface};
+use crate::zone::ZoneEffects;
 use crate::{actor, GameState};
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
@@ -12,6 +14,10 @@ pub struct GhostPlugin;
 #[derive(Component, Default, Clone)]
 pub struct Ghost {
     move_left: bool,
+    pub from_soul: bool,
+    pub soul_lifetime: Option<f32>,
+    pub soul_returns: bool,
+    pub soul_group: Option<String>,
 }
 
 /// This plugin handles player related stuff like movement
@@ -45,6 +51,63 @@ pub struct GhostBundle {
     pub squashy: Squashy,
 }
 
+impl GhostBundle {
+    pub fn new(texture_atlas: Handle<TextureAtlas>, actor: Actor, ghost: Ghost) -> Self {
+        GhostBundle {
+            sprite_sheet_bundle: SpriteSheetBundle {
+                texture_atlas,
+                transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
+                ..Default::default()
+            },
+            sprite_animator: crate::sprite_anim::SpriteAnimator::new(0, 3, 4, 0.2, true, true),
+            ghost,
+            rigidbody: RigidBody::KinematicPositionBased,
+            collider: Collider::capsule_y(5.0, 5.0),
+            label: Labeled {
+                name: String::from("ghost"),
+            },
+            controller: KinematicCharacterController {
+                offset: CharacterLength::Absolute(0.2),
+                autostep: None,
+                filter_flags: QueryFilterFlags::EXCLUDE_SENSORS,
+                ..Default::default()
+            },
+            actor,
+            actor_status: ActorStatus {
+                grounded: false,
+                facing_left: false,
+                velocity: Vec2::ZERO,
+                air_timer: 0.,
+                air_jumps_used: 0,
+                attack_direction: None,
+                attack_timer: 0.,
+                left_wall: false,
+                right_wall: false,
+                event: None,
+                last_dt: 1.,
+                surface: Surface::Normal,
+                zone: ZoneEffects::default(),
+            },
+            death: TouchDeath,
+            scythable: Scythable {
+                scythed: false,
+                hit_from: None,
+            },
+            squashy: Squashy {
+                base_scale: Vec2::new(24., 24.),
+                restore_time: 0.25,
+                squash_scale: Vec2::new(1.2, 0.7),
+                squash_time: 0.05,
+                stretch_scale: Vec2::new(0.8, 1.1),
+                stretch_time: 0.05,
+                state: None,
+                state_time: 0.,
+                from_pos: Vec2::ONE,
+            },
+        }
+    }
+}
+
 impl LdtkEntity for GhostBundle {
     fn bundle_entity(
         entity_instance: &EntityInstance,
@@ -114,59 +177,26 @@ impl LdtkEntity for GhostBundle {
                         ghost.move_left = value;
                     }
                 }
+                "SoulLifetime" => {
+                    if let FieldValue::Float(value) = field.value {
+                        ghost.soul_lifetime = value.filter(|lifetime| *lifetime > 0.);
+                    }
+                }
+                "SoulReturns" => {
+                    if let FieldValue::Bool(value) = field.value {
+                        ghost.soul_returns = value;
+                    }
+                }
+                "SoulGroup" => {
+                    if let FieldValue::String(value) = &field.value {
+                        ghost.soul_group = value.clone().filter(|group| !group.is_empty());
+                    }
+                }
                 unknown => println!("Unknown field \"{}\" on LDtk ghost object!", unknown),
             }
         }
 
-        GhostBundle {
-            sprite_sheet_bundle: SpriteSheetBundle {
-                texture_atlas: texture_atlas_handle.clone(),
-                transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
-                ..Default::default()
-            },
-            sprite_animator: crate::sprite_anim::SpriteAnimator::new(0, 3, 4, 0.2, true, true),
-            ghost,
-            rigidbody: RigidBody::KinematicPositionBased,
-            collider: Collider::capsule_y(5.0, 5.0),
-            label: Labeled {
-                name: String::from("ghost"),
-            },
-            controller: KinematicCharacterController {
-                offset: CharacterLength::Absolute(0.2),
-                autostep: None,
-                filter_flags: QueryFilterFlags::EXCLUDE_SENSORS,
-                ..Default::default()
-            },
-            actor,
-            actor_status: ActorStatus {
-                grounded: false,
-                facing_left: false,
-                velocity: Vec2::ZERO,
-                air_timer: 0.,
-                attack_direction: None,
-                attack_timer: 0.,
-                left_wall: false,
-                right_wall: false,
-                event: None,
-                last_dt: 1.,
-            },
-            death: TouchDeath,
-            scythable: Scythable {
-                scythed: false,
-                hit_from: None,
-            },
-            squashy: Squashy {
-                base_scale: Vec2::new(24., 24.),
-                restore_time: 0.25,
-                squash_scale: Vec2::new(1.2, 0.7),
-                squash_time: 0.05,
-                stretch_scale: Vec2::new(0.8, 1.1),
-                stretch_time: 0.05,
-                state: None,
-                state_time: 0.,
-                from_pos: Vec2::ONE,
-            },
-        }
+        GhostBundle::new(texture_atlas_handle, actor, ghost)
     }
 }
 
@@ -183,12 +213,12 @@ fn ghost_movement(mut ghost_query: Query<(&mut Ghost, &mut Actor, &ActorStatus)>
 }
 
 fn ghost_death(
-    ghost_query: Query<(Entity, &Transform, &Scythable), With<Ghost>>,
+    ghost_query: Query<(Entity, &GlobalTransform, &Scythable, &Ghost, &Actor)>,
     mut commands: Commands,
     sprites: Res<crate::loading::SpriteAssets>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
 ) {
-    for (entity, transform, scythable) in &ghost_query {
+    for (entity, transform, scythable, ghost, actor) in &ghost_query {
         if scythable.scythed {
             commands.entity(entity).despawn_recursive();
 
@@ -198,15 +228,16 @@ fn ghost_death(
             let texture_atlas_handle = texture_atlases.add(texture_atlas);
 
             let escape_vec = if let Some(hit_from) = scythable.hit_from {
-                160. * (transform.translation.truncate() - hit_from).normalize_or_zero()
+                160. * (transform.translation().truncate() - hit_from).normalize_or_zero()
             } else {
                 Vec2::new(0., 60.)
             };
 
-            commands.spawn(crate::soul::SoulBundle {
+            let mut soul_commands = commands.spawn(crate::soul::SoulBundle {
                 sprite_sheet_bundle: SpriteSheetBundle {
                     texture_atlas: texture_atlas_handle,
-                    transform: Transform::from_translation(transform.translation),
+                    // Released souls live outside the level, so they need the ghost's world position
+                    transform: Transform::from_translation(transform.translation()),
                     ..Default::default()
                 },
                 sprite_animator: crate::sprite_anim::SpriteAnimator::new(0, 3, 4, 0.2, true, true),
@@ -216,6 +247,9 @@ fn ghost_death(
                     move_speed: 160.,
                     velocity: escape_vec,
                     from_ghost: true,
+                    tier: SoulTier::Small,
+                    value: SoulTier::Small.default_value(),
+                    group: ghost.soul_group.clone(),
                 },
                 rigidbody: RigidBody::KinematicPositionBased,
                 collider: Collider::ball(5.),
@@ -229,10 +263,29 @@ fn ghost_death(
                     filter_flags: QueryFilterFlags::EXCLUDE_SENSORS,
                     ..Default::default()
                 },
-                pickup: crate::pickup::Pickup {
-                    pickup_type: Some(crate::pickup::PickupType::Soul),
-                },
+                pickup: crate::pickup::Pickup::new(crate::pickup::PickupType::Soul {
+                    tier: SoulTier::Small,
+                    value: SoulTier::Small.default_value(),
+                    group: ghost.soul_group.clone(),
+                })
+                .with_delay(0.4),
             });
+
+            if let Some(lifetime) = ghost.soul_lifetime {
+                let fate = if ghost.soul_returns {
+                    SoulFate::ReturnToGhost {
+                        ghost: Ghost {
+                            from_soul: true,
+                            ..ghost.clone()
+                        },
+                        actor: actor.clone(),
+                    }
+                } else {
+                    SoulFate::Escape
+                };
+
+                soul_commands.insert(SoulDecay::new(lifetime, fate));
+            }
         }
     }
 }
@@ -0,0 +1,241 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::actor::Scythable;
+use crate::loading::{FontAssets, SpriteAssets};
+use crate::pickup::{CollectedGrainEvent, PickupBundle, PickupType};
+use crate::save::SaveFile;
+use crate::world::Labeled;
+use crate::GameState;
+
+pub struct HarvestPlugin;
+
+pub const GRAIN_COLOR: Color = Color::rgb(0.91, 0.76, 0.35);
+
+/// Grain the player has harvested, kept between levels and runs like completed levels are
+#[derive(Resource, Default, Debug)]
+pub struct Granary {
+    pub grain: usize,
+}
+
+impl Granary {
+    /// Takes the grain if there is enough of it
+    pub fn spend(&mut self, amount: usize) -> bool {
+        if self.grain >= amount {
+            self.grain -= amount;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[derive(Component, Clone, Debug)]
+pub struct Wheat {
+    pub grain: usize,
+    pub regrow_time: f32,
+    /// Counts down while the wheat is cut, it can be harvested again once it has grown back
+    pub regrow_timer: Option<f32>,
+}
+
+impl Default for Wheat {
+    fn default() -> Self {
+        Wheat {
+            grain: 1,
+            regrow_time: 20.,
+            regrow_timer: None,
+        }
+    }
+}
+
+impl Wheat {
+    /// Takes the instance by value, the way `#[with(...)]` on an `LdtkEntity` field passes it
+    pub fn from_entity_instance(entity_instance: EntityInstance) -> Self {
+        let mut wheat = Wheat::default();
+
+        for field in entity_instance.field_instances.iter() {
+            match field.identifier.as_str() {
+                "Grain" => {
+                    if let FieldValue::Int(Some(value)) = field.value {
+                        wheat.grain = value.max(0) as usize;
+                    }
+                }
+                "RegrowTime" => {
+                    if let FieldValue::Float(Some(value)) = field.value {
+                        wheat.regrow_time = value;
+                    }
+                }
+                unknown => println!("Unknown field \"{}\" on LDtk wheat object!", unknown),
+            }
+        }
+
+        wheat
+    }
+}
+
+#[derive(Clone, Default, Bundle, LdtkEntity)]
+pub struct WheatBundle {
+    pub collider: Collider,
+    pub label: Labeled,
+    pub sensor: Sensor,
+    pub active_events: ActiveEvents,
+    pub rotation_constraints: LockedAxes,
+    pub scythable: Scythable,
+    #[with(Wheat::from_entity_instance)]
+    pub wheat: Wheat,
+    #[sprite_bundle("sprites/wheat_grown.png")]
+    #[bundle]
+    pub sprite_bundle: SpriteBundle,
+}
+
+#[derive(Component)]
+struct GrainHud;
+
+impl Plugin for HarvestPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Granary>()
+            .add_system_set(
+                SystemSet::on_enter(GameState::Playing)
+                    .with_system(load_granary)
+                    .with_system(setup_grain_hud),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Shop).with_system(load_granary))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(cut_wheat)
+                    .with_system(regrow_wheat)
+                    .with_system(collect_grain)
+                    .with_system(update_grain_hud),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(save_granary)
+                    .after(collect_grain)
+                    .after(crate::player::player_win),
+            );
+    }
+}
+
+fn load_granary(mut granary: ResMut<Granary>, save_file: Res<SaveFile>) {
+    granary.grain = save_file.grain;
+}
+
+fn save_granary(granary: Res<Granary>, mut save_file: ResMut<SaveFile>) {
+    if granary.is_changed() && save_file.grain != granary.grain {
+        save_file.grain = granary.grain;
+    }
+}
+
+/// Cut wheat drops its grain and starts growing back
+fn cut_wheat(
+    mut commands: Commands,
+    mut wheat_query: Query<(
+        &Parent,
+        &Transform,
+        &mut Wheat,
+        &mut Scythable,
+        &mut Handle<Image>,
+    )>,
+    sprites: Res<SpriteAssets>,
+    asset_server: Res<AssetServer>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    for (parent, transform, mut wheat, mut scythable, mut image) in &mut wheat_query {
+        if !scythable.scythed {
+            continue;
+        }
+
+        scythable.scythed = false;
+
+        if wheat.regrow_timer.is_some() {
+            continue;
+        }
+
+        *image = sprites.texture_wheat_chopped.clone();
+        wheat.regrow_timer = Some(wheat.regrow_time);
+
+        if wheat.grain == 0 {
+            continue;
+        }
+
+        let mut grain = PickupBundle::new(
+            PickupType::Grain {
+                amount: wheat.grain,
+            },
+            String::from("grain"),
+            &asset_server,
+            &mut texture_atlases,
+        );
+        grain.sprite_sheet_bundle.transform.translation += transform.translation;
+        // Give the player a moment to see it drop before it's collected
+        grain.pickup.delay = 0.3;
+
+        // Spawned next to the wheat so it's cleaned up with the level
+        commands.entity(parent.get()).with_children(|layer| {
+            layer.spawn(grain);
+        });
+    }
+}
+
+fn regrow_wheat(
+    time: Res<Time>,
+    mut wheat_query: Query<(&mut Wheat, &mut Handle<Image>)>,
+    sprites: Res<SpriteAssets>,
+) {
+    for (mut wheat, mut image) in &mut wheat_query {
+        if let Some(timer) = wheat.regrow_timer.as_mut() {
+            *timer -= time.delta_seconds();
+
+            if *timer <= 0. {
+                wheat.regrow_timer = None;
+                *image = sprites.texture_wheat_grown.clone();
+            }
+        }
+    }
+}
+
+fn collect_grain(mut grain_events: EventReader<CollectedGrainEvent>, mut granary: ResMut<Granary>) {
+    for ev in grain_events.iter() {
+        granary.grain += ev.amount;
+    }
+}
+
+fn setup_grain_hud(mut commands: Commands, fonts: Res<FontAssets>) {
+    commands
+        .spawn(TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: fonts.press_start.clone(),
+                    font_size: 16.,
+                    color: GRAIN_COLOR,
+                },
+            ),
+            style: Style {
+                position: UiRect {
+                    right: Val::Px(16.),
+                    top: Val::Px(16.),
+                    ..Default::default()
+                },
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(GrainHud);
+}
+
+fn update_grain_hud(granary: Res<Granary>, mut hud_query: Query<&mut Text, With<GrainHud>>) {
+    if !granary.is_changed() {
+        return;
+    }
+
+    for mut text in &mut hud_query {
+        text.sections[0].value = if granary.grain > 0 {
+            format!("{} grain", granary.grain)
+        } else {
+            String::new()
+        };
+    }
+}
@@ -0,0 +1,61 @@
+use bevy::prelude::*;
+
+use crate::gate::{key_color, KeyRing};
+use crate::player::Player;
+use crate::GameState;
+
+pub struct HudPlugin;
+
+/// Row of coloured squares showing the keys the player is carrying
+#[derive(Component)]
+struct KeyHud;
+
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_enter(GameState::Playing).with_system(setup_hud))
+            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(update_key_hud));
+    }
+}
+
+fn setup_hud(mut commands: Commands) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position: UiRect {
+                    left: Val::Px(16.),
+                    top: Val::Px(16.),
+                    ..Default::default()
+                },
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Row,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(KeyHud);
+}
+
+fn update_key_hud(
+    key_rings: Query<&KeyRing, (With<Player>, Changed<KeyRing>)>,
+    hud_query: Query<Entity, With<KeyHud>>,
+    mut commands: Commands,
+) {
+    for key_ring in &key_rings {
+        for hud in &hud_query {
+            commands.entity(hud).despawn_descendants();
+            commands.entity(hud).with_children(|parent| {
+                for key in key_ring.keys.iter() {
+                    parent.spawn(NodeBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(16.), Val::Px(16.)),
+                            margin: UiRect::all(Val::Px(4.)),
+                            ..Default::default()
+                        },
+                        background_color: key_color(key).into(),
+                        ..Default::default()
+                    });
+                }
+            });
+        }
+    }
+}
@@ -1,28 +1,62 @@
 mod actions;
 mod loading;
+mod magnet;
+mod mechanism;
 mod player;
 // mod mainmenu;
 mod actor;
+mod background;
+mod breakable;
 mod camera;
+mod checkpoint;
+mod collision;
+mod designer;
 mod door;
+mod gate;
 mod ghost;
+mod harvest;
+mod hud;
 mod menu;
 mod pickup;
+mod progression;
+mod save;
+mod secret;
 mod settings;
+mod signal;
 mod soul;
 mod sprite_anim;
+mod streaming;
 mod ui_events;
+mod upgrade;
+pub mod validation;
 mod world;
+mod zone;
 
 use crate::actions::ActionsPlugin;
+use crate::background::BackgroundPlugin;
+use crate::breakable::BreakablePlugin;
 use crate::camera::CameraPlugin;
+use crate::checkpoint::CheckpointPlugin;
+use crate::designer::DesignerPlugin;
+use crate::gate::GatePlugin;
 use crate::ghost::GhostPlugin;
+use crate::harvest::HarvestPlugin;
+use crate::hud::HudPlugin;
 use crate::loading::LoadingPlugin;
+use crate::magnet::MagnetPlugin;
+use crate::mechanism::MechanismPlugin;
 use crate::menu::MenuPlugin;
 use crate::pickup::PickupPlugin;
 use crate::player::PlayerPlugin;
+use crate::progression::ProgressionPlugin;
+use crate::save::SavePlugin;
+use crate::secret::SecretPlugin;
 use crate::settings::SettingsPlugin;
+use crate::signal::SignalPlugin;
+use crate::streaming::StreamingPlugin;
+use crate::upgrade::UpgradePlugin;
 use crate::world::WorldPlugin;
+use crate::zone::ZonePlugin;
 
 use actor::ActorPlugin;
 use bevy::app::App;
@@ -49,6 +83,10 @@ enum GameState {
     Paused,
     // Shows level selection menu
     LevelSelect,
+    // Spends saved grain on upgrades between levels
+    Shop,
+    // Picks, copies and deletes save slots
+    SaveSlots,
     // Shows win screen, links back to main menu
     WinScreen,
 }
@@ -59,18 +97,34 @@ impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_state(GameState::Loading)
             .add_plugin(SettingsPlugin)
+            .add_plugin(SavePlugin)
             .add_plugin(WorldPlugin)
+            .add_plugin(ProgressionPlugin)
             .add_plugin(LoadingPlugin)
             .add_plugin(UiEventPlugin)
             .add_plugin(MenuPlugin)
             .add_plugin(ActionsPlugin)
             .add_plugin(PlayerPlugin)
             .add_plugin(PickupPlugin)
+            .add_plugin(HarvestPlugin)
+            .add_plugin(UpgradePlugin)
             .add_plugin(GhostPlugin)
             .add_plugin(SoulPlugin)
+            .add_plugin(MagnetPlugin)
+            .add_plugin(GatePlugin)
+            .add_plugin(SignalPlugin)
+            .add_plugin(MechanismPlugin)
+            .add_plugin(ZonePlugin)
+            .add_plugin(BreakablePlugin)
+            .add_plugin(SecretPlugin)
+            .add_plugin(CheckpointPlugin)
+            .add_plugin(StreamingPlugin)
+            .add_plugin(DesignerPlugin)
+            .add_plugin(HudPlugin)
             .add_plugin(ActorPlugin)
             .add_plugin(SpriteAnimationPlugin)
-            .add_plugin(CameraPlugin);
+            .add_plugin(CameraPlugin)
+            .add_plugin(BackgroundPlugin);
 
         #[cfg(debug_assertions)]
         {
@@ -1,6 +1,7 @@
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
 
 pub struct LoadingPlugin;
 
@@ -14,6 +15,7 @@ impl Plugin for LoadingPlugin {
                 .with_collection::<FontAssets>()
                 .with_collection::<AudioAssets>()
                 .with_collection::<SpriteAssets>()
+                .with_collection::<LevelAssets>()
                 .continue_to_state(GameState::Menu),
         );
     }
@@ -22,6 +24,12 @@ impl Plugin for LoadingPlugin {
 // the following asset collections will be loaded during the State `GameState::Loading`
 // when done loading, they will be inserted as resources (see <https://github.com/NiklasEi/bevy_asset_loader>)
 
+#[derive(AssetCollection, Resource)]
+pub struct LevelAssets {
+    #[asset(path = "levels/World.ldtk")]
+    pub world: Handle<LdtkAsset>,
+}
+
 #[derive(AssetCollection, Resource)]
 pub struct FontAssets {
     #[asset(path = "fonts/PressStart2P.ttf")]
@@ -46,6 +54,14 @@ pub struct AudioAssets {
     pub win: Handle<AudioSource>,
     #[asset(path = "audio/soul_pickup2.ogg")]
     pub pickup: Handle<AudioSource>,
+    #[asset(path = "audio/soul_pickup.ogg")]
+    pub big_pickup: Handle<AudioSource>,
+    #[asset(path = "audio/unlocked.ogg")]
+    pub key_pickup: Handle<AudioSource>,
+    #[asset(path = "audio/pickup1.ogg")]
+    pub health_pickup: Handle<AudioSource>,
+    #[asset(path = "audio/victory.ogg")]
+    pub secret_pickup: Handle<AudioSource>,
     #[asset(path = "audio/unlocked3.ogg")]
     pub unlocked: Handle<AudioSource>,
 }
@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use bevy::sprite::MaterialMesh2dBundle;
+
+use crate::actions::Actions;
+use crate::player::Player;
+use crate::save::SaveFile;
+use crate::GameState;
+
+pub struct MagnetPlugin;
+
+/// Pulls nearby souls toward the player while the magnet input is held
+#[derive(Component, Clone)]
+pub struct SoulMagnet {
+    pub unlocked: bool,
+    pub active: bool,
+    pub radius: f32,
+    pub pull_speed: f32,
+}
+
+#[derive(Component, Default, Clone)]
+pub struct MagnetIndicator;
+
+impl SoulMagnet {
+    pub const ABILITY_NAME: &'static str = "SoulMagnet";
+
+    /// Velocity a soul at `soul_pos` should be pulled with, if it is in range
+    pub fn pull_on(&self, magnet_pos: Vec2, soul_pos: Vec2) -> Option<Vec2> {
+        if !self.unlocked || !self.active {
+            return None;
+        }
+
+        let to_magnet = magnet_pos - soul_pos;
+        let distance = to_magnet.length();
+
+        if distance < self.radius {
+            // Pull harder the closer the soul gets, so it doesn't orbit the player
+            let closeness = 1. - (distance / self.radius);
+            Some(to_magnet.normalize_or_zero() * self.pull_speed * (0.5 + closeness))
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for SoulMagnet {
+    fn default() -> Self {
+        SoulMagnet {
+            unlocked: false,
+            active: false,
+            radius: 96.,
+            pull_speed: 180.,
+        }
+    }
+}
+
+impl Plugin for MagnetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(magnet_unlocks)
+                .with_system(magnet_inputs)
+                .after(crate::actions::set_movement_actions)
+                .before(crate::soul::soul_movement),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(magnet_indicator)
+                .after(magnet_inputs),
+        );
+    }
+}
+
+fn magnet_unlocks(
+    mut magnet_query: Query<(Entity, &mut SoulMagnet), Added<SoulMagnet>>,
+    mut save_file: ResMut<SaveFile>,
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, mut magnet) in &mut magnet_query {
+        if magnet.unlocked {
+            // Granted by the level, remember it for every level after this one
+            if !save_file.has_unlocked_ability(SoulMagnet::ABILITY_NAME) {
+                save_file.unlock_ability(SoulMagnet::ABILITY_NAME);
+            }
+        } else {
+            magnet.unlocked = save_file.has_unlocked_ability(SoulMagnet::ABILITY_NAME);
+        }
+
+        if magnet.unlocked {
+            commands
+                .spawn(MaterialMesh2dBundle {
+                    mesh: meshes.add(shape::Circle::new(magnet.radius).into()).into(),
+                    material: materials.add(ColorMaterial::from(Color::rgba(
+                        0.35, 0.68, 0.75, 0.15,
+                    ))),
+                    transform: Transform::from_xyz(0., 0., -0.5),
+                    visibility: Visibility { is_visible: false },
+                    ..Default::default()
+                })
+                .insert(MagnetIndicator)
+                .set_parent(entity);
+        }
+    }
+}
+
+fn magnet_inputs(actions: Res<Actions>, mut magnet_query: Query<&mut SoulMagnet, With<Player>>) {
+    for mut magnet in &mut magnet_query {
+        let active = magnet.unlocked && actions.magnet;
+
+        // Only write on change so the indicator can watch for Changed<SoulMagnet>
+        if magnet.active != active {
+            magnet.active = active;
+        }
+    }
+}
+
+fn magnet_indicator(
+    magnet_query: Query<(&SoulMagnet, &Children), Changed<SoulMagnet>>,
+    mut indicator_query: Query<&mut Visibility, With<MagnetIndicator>>,
+) {
+    for (magnet, children) in &magnet_query {
+        for child in children.iter() {
+            if let Ok(mut visibility) = indicator_query.get_mut(*child) {
+                visibility.is_visible = magnet.active;
+            }
+        }
+    }
+}
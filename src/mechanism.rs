@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::player::TouchDeath;
+use crate::signal::SignalReceiver;
+use crate::world::Labeled;
+use crate::GameState;
+
+pub struct MechanismPlugin;
+
+/// Slides between where it was placed and `travel` away from it while its signal is active
+#[derive(Component, Default, Clone, Debug)]
+pub struct MovingPlatform {
+    pub start: Option<Vec3>,
+    pub travel: Vec2,
+    pub speed: f32,
+    pub progress: f32,
+}
+
+/// Spikes that can be retracted by a signal, extended while active
+#[derive(Component, Default, Clone, Debug)]
+pub struct SpikeTrap {
+    pub half_extents: Vec2,
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct MovingPlatformBundle {
+    #[bundle]
+    pub sprite_bundle: SpriteBundle,
+    pub collider: Collider,
+    pub rigidbody: RigidBody,
+    pub label: Labeled,
+    pub platform: MovingPlatform,
+    pub receiver: SignalReceiver,
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct SpikeTrapBundle {
+    #[bundle]
+    pub sprite_bundle: SpriteBundle,
+    pub collider: Collider,
+    pub label: Labeled,
+    pub trap: SpikeTrap,
+    pub death: TouchDeath,
+    pub receiver: SignalReceiver,
+}
+
+impl Plugin for MechanismPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(move_platforms)
+                .with_system(sync_spike_traps)
+                .after(crate::signal::receive_signals),
+        );
+    }
+}
+
+impl LdtkEntity for MovingPlatformBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        _layer_instance: &LayerInstance,
+        _tileset: Option<&Handle<Image>>,
+        _tileset_definition: Option<&TilesetDefinition>,
+        _asset_server: &AssetServer,
+        _texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Self {
+        let size = Vec2::new(entity_instance.width as f32, entity_instance.height as f32);
+        let mut platform = MovingPlatform {
+            speed: 40.,
+            ..Default::default()
+        };
+
+        for field in entity_instance.field_instances.iter() {
+            match field.identifier.as_str() {
+                "TravelX" => {
+                    if let FieldValue::Float(Some(value)) = field.value {
+                        platform.travel.x = value;
+                    }
+                }
+                "TravelY" => {
+                    if let FieldValue::Float(Some(value)) = field.value {
+                        platform.travel.y = value;
+                    }
+                }
+                "Speed" => {
+                    if let FieldValue::Float(Some(value)) = field.value {
+                        platform.speed = value;
+                    }
+                }
+                "StartActive" | "ResetTime" => (),
+                unknown => println!("Unknown field \"{}\" on LDtk platform object!", unknown),
+            }
+        }
+
+        MovingPlatformBundle {
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite {
+                    color: Color::hex("5a6988").unwrap(),
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            collider: Collider::cuboid(size.x / 2., size.y / 2.),
+            rigidbody: RigidBody::KinematicPositionBased,
+            label: Labeled {
+                name: String::from("platform"),
+            },
+            platform,
+            receiver: SignalReceiver::from_entity_instance(entity_instance, false),
+        }
+    }
+}
+
+impl LdtkEntity for SpikeTrapBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        _layer_instance: &LayerInstance,
+        _tileset: Option<&Handle<Image>>,
+        _tileset_definition: Option<&TilesetDefinition>,
+        _asset_server: &AssetServer,
+        _texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Self {
+        let trap = SpikeTrap {
+            half_extents: Vec2::new(
+                entity_instance.width as f32 / 2.,
+                entity_instance.height as f32 / 4.,
+            ),
+        };
+
+        for field in entity_instance.field_instances.iter() {
+            match field.identifier.as_str() {
+                "StartActive" | "ResetTime" => (),
+                unknown => println!("Unknown field \"{}\" on LDtk spike trap object!", unknown),
+            }
+        }
+
+        SpikeTrapBundle {
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite {
+                    color: Color::hex("a22633").unwrap(),
+                    custom_size: Some(trap.half_extents * 2.),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(Vec3::new(0., 0., 0.5)),
+                ..Default::default()
+            },
+            collider: Collider::cuboid(trap.half_extents.x, trap.half_extents.y),
+            label: Labeled {
+                name: String::from("spikes"),
+            },
+            trap,
+            death: TouchDeath,
+            receiver: SignalReceiver::from_entity_instance(entity_instance, true),
+        }
+    }
+}
+
+fn move_platforms(
+    time: Res<Time>,
+    mut platforms: Query<(&mut MovingPlatform, &SignalReceiver, &mut Transform)>,
+) {
+    for (mut platform, receiver, mut transform) in &mut platforms {
+        // LDtk positions the entity after the bundle is built, so remember it on the first frame
+        let start = *platform.start.get_or_insert(transform.translation);
+
+        let distance = platform.travel.length();
+        if distance <= 0. {
+            continue;
+        }
+
+        let target = if receiver.active { 1. } else { 0. };
+        let step = platform.speed / distance * time.delta_seconds();
+
+        platform.progress = if platform.progress < target {
+            (platform.progress + step).min(target)
+        } else {
+            (platform.progress - step).max(target)
+        };
+
+        transform.translation = start + (platform.travel * platform.progress).extend(0.);
+    }
+}
+
+fn sync_spike_traps(
+    mut traps: Query<(Entity, &SpikeTrap, &SignalReceiver, &mut Visibility), Changed<SignalReceiver>>,
+    mut commands: Commands,
+) {
+    for (entity, trap, receiver, mut visibility) in &mut traps {
+        visibility.is_visible = receiver.active;
+
+        if receiver.active {
+            commands
+                .entity(entity)
+                .insert(TouchDeath)
+                .insert(Collider::cuboid(trap.half_extents.x, trap.half_extents.y));
+        } else {
+            commands
+                .entity(entity)
+                .remove::<TouchDeath>()
+                .remove::<Collider>();
+        }
+    }
+}
@@ -1,6 +1,11 @@
 use crate::actions::Actions;
+use crate::harvest::{Granary, GRAIN_COLOR};
 use crate::loading::*;
+use crate::progression::LevelGraph;
+use crate::save::{SaveFile, SaveSlot, SaveTransferStatus};
+use crate::secret::SecretIndex;
 use crate::settings::Settings;
+use crate::upgrade::Upgrade;
 use crate::GameState;
 use bevy::app::AppExit;
 use bevy::prelude::*;
@@ -16,17 +21,42 @@ pub struct MenuPlugin;
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<ButtonColors>()
+            .init_resource::<SlotMenu>()
             .add_plugins(DefaultNavigationPlugins)
             .add_system(button_system.after(NavRequestSystem))
             .add_system(button_nav_events.after(NavRequestSystem))
+            .add_system(slot_nav_events.after(NavRequestSystem))
+            .add_system(update_save_transfer_text.after(button_nav_events))
             .add_system_set(SystemSet::on_enter(GameState::Menu).with_system(setup_menu))
-            .add_system_set(SystemSet::on_exit(GameState::Menu).with_system(cleanup_menu))
+            .add_system_set(
+                SystemSet::on_exit(GameState::Menu)
+                    .with_system(cleanup_menu)
+                    .with_system(clear_save_transfer_status),
+            )
             .add_system_set(
                 SystemSet::on_enter(GameState::LevelSelect).with_system(setup_level_select),
             )
             .add_system_set(SystemSet::on_update(GameState::LevelSelect).with_system(back_to_menu))
             .add_system_set(SystemSet::on_exit(GameState::LevelSelect).with_system(cleanup_menu))
-            .add_system_set(SystemSet::on_enter(GameState::Paused).with_system(setup_pause_menu))
+            .add_system_set(SystemSet::on_enter(GameState::Shop).with_system(setup_shop))
+            .add_system_set(
+                SystemSet::on_update(GameState::Shop)
+                    .with_system(leave_shop)
+                    .with_system(update_shop_labels),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Shop).with_system(cleanup_menu))
+            .add_system_set(SystemSet::on_enter(GameState::SaveSlots).with_system(setup_save_slots))
+            .add_system_set(
+                SystemSet::on_update(GameState::SaveSlots)
+                    .with_system(back_to_menu)
+                    .with_system(update_slot_labels),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::SaveSlots).with_system(cleanup_menu))
+            .add_system_set(
+                SystemSet::on_enter(GameState::Paused)
+                    .with_system(clear_save_transfer_status)
+                    .with_system(setup_pause_menu.after(clear_save_transfer_status)),
+            )
             .add_system_set(SystemSet::on_update(GameState::Paused).with_system(esc_to_resume))
             .add_system_set(SystemSet::on_exit(GameState::Paused).with_system(cleanup_menu))
             .add_system_set(SystemSet::on_update(GameState::Playing).with_system(esc_to_menu))
@@ -56,6 +86,14 @@ impl Default for ButtonColors {
 enum MenuButton {
     Play(usize),
     LevelSelect,
+    Shop,
+    Buy(Upgrade),
+    SaveSlots,
+    Slot(usize),
+    CopySlot(usize),
+    DeleteSlot(usize),
+    ExportSave,
+    ImportSave,
     Options,
     Menu,
     Resume,
@@ -72,11 +110,98 @@ enum SettingToggle {
 #[derive(Component)]
 struct MenuElement;
 
+#[derive(Component)]
+struct ShopGrainText;
+
+/// Says whether the last save export or import worked, and why not if it didn't
+#[derive(Component)]
+struct SaveTransferText;
+
+/// What the save slot screen is in the middle of
+#[derive(Resource, Default)]
+struct SlotMenu {
+    /// Each slot's save, read when the screen opens and after every change
+    slots: Vec<SaveFile>,
+    /// Slot being copied, the next slot picked is where it goes
+    copying: Option<usize>,
+    /// Action that needs pressing again before it overwrites a save
+    confirming: Option<SlotAction>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum SlotAction {
+    Copy { from: usize, to: usize },
+    Delete(usize),
+}
+
+impl SlotMenu {
+    fn reload(&mut self, data_store: &PkvStore, save_slot: &SaveSlot, save_file: &SaveFile) {
+        self.slots = (0..SaveFile::SLOTS)
+            .map(|slot| {
+                if slot == save_slot.index {
+                    save_file.clone()
+                } else {
+                    SaveFile::load(data_store, slot)
+                }
+            })
+            .collect();
+    }
+
+    fn label(
+        &self,
+        button: &MenuButton,
+        save_slot: &SaveSlot,
+        level_count: usize,
+    ) -> Option<String> {
+        let label = match *button {
+            MenuButton::Slot(slot) => {
+                if matches!(self.confirming, Some(SlotAction::Copy { to, .. }) if to == slot) {
+                    format!("Overwrite\nSlot {}?", slot + 1)
+                } else if self.copying.is_some() && self.copying != Some(slot) {
+                    format!("Copy to\nSlot {}", slot + 1)
+                } else {
+                    let save_file = self.slots.get(slot)?;
+                    let name = if slot == save_slot.index {
+                        format!("> Slot {} <", slot + 1)
+                    } else {
+                        format!("Slot {}", slot + 1)
+                    };
+
+                    if save_file.read_only {
+                        format!("{}\nRead only", name)
+                    } else if save_file.is_empty() {
+                        format!("{}\nEmpty", name)
+                    } else {
+                        format!(
+                            "{}\n{}% {}",
+                            name,
+                            save_file.completion_percent(level_count),
+                            save_file.play_time_label()
+                        )
+                    }
+                }
+            }
+            MenuButton::CopySlot(slot) if self.copying == Some(slot) => String::from("Cancel"),
+            MenuButton::CopySlot(_) => String::from("Copy"),
+            MenuButton::DeleteSlot(slot) if self.confirming == Some(SlotAction::Delete(slot)) => {
+                String::from("Sure?")
+            }
+            MenuButton::DeleteSlot(_) => String::from("Delete"),
+            _ => return None,
+        };
+
+        Some(label)
+    }
+}
+
 fn setup_menu(
     mut commands: Commands,
     font_assets: Res<FontAssets>,
     button_colors: Res<ButtonColors>,
     sprite_assets: Res<SpriteAssets>,
+    level_graph: Res<LevelGraph>,
+    save_file: Res<SaveFile>,
+    transfer_status: Res<SaveTransferStatus>,
 ) {
     spawn_menu_button(
         &mut commands,
@@ -95,6 +220,14 @@ fn setup_menu(
         Vec2::new(19., 8.),
     );
     // spawn_menu_button(&mut commands, &button_colors, &font_assets.press_start, MenuButton::Options, Vec2::new(50., 80.),Vec2::new(19., 8.));
+    spawn_menu_button(
+        &mut commands,
+        &button_colors,
+        &font_assets.press_start,
+        MenuButton::Shop,
+        Vec2::new(50., 80.),
+        Vec2::new(19., 8.),
+    );
     spawn_menu_button(
         &mut commands,
         &button_colors,
@@ -103,6 +236,49 @@ fn setup_menu(
         Vec2::new(70., 80.),
         Vec2::new(19., 8.),
     );
+    spawn_menu_button(
+        &mut commands,
+        &button_colors,
+        &font_assets.press_start,
+        MenuButton::SaveSlots,
+        Vec2::new(50., 68.),
+        Vec2::new(19., 8.),
+    );
+    spawn_menu_button(
+        &mut commands,
+        &button_colors,
+        &font_assets.press_start,
+        MenuButton::ExportSave,
+        Vec2::new(30., 68.),
+        Vec2::new(19., 8.),
+    );
+    spawn_menu_button(
+        &mut commands,
+        &button_colors,
+        &font_assets.press_start,
+        MenuButton::ImportSave,
+        Vec2::new(70., 68.),
+        Vec2::new(19., 8.),
+    );
+    spawn_save_transfer_text(
+        &mut commands,
+        &font_assets.press_start,
+        &transfer_status.message,
+        Vec2::new(20., 90.),
+    );
+
+    // Picks up after the last level the player finished, following the progression graph
+    let next_level = level_graph.next_level(|level| save_file.has_completed_level(level));
+    if let Some(level_index) = next_level.filter(|level| level_graph.order.first() != Some(level)) {
+        spawn_menu_button(
+            &mut commands,
+            &button_colors,
+            &font_assets.press_start,
+            MenuButton::Play(level_index),
+            Vec2::new(10., 68.),
+            Vec2::new(19., 8.),
+        );
+    }
 
     commands
         .spawn(ImageBundle {
@@ -192,11 +368,25 @@ fn back_to_menu(mut actions: ResMut<Actions>, mut state: ResMut<State<GameState>
     }
 }
 
+/// The shop either opens from the main menu or between levels, going back to wherever it was
+fn leave_shop(mut actions: ResMut<Actions>, mut state: ResMut<State<GameState>>) {
+    if actions.back {
+        actions.back = false;
+
+        if state.inactives().is_empty() {
+            state.replace(GameState::Menu).unwrap();
+        } else {
+            state.pop().unwrap();
+        }
+    }
+}
+
 fn setup_pause_menu(
     mut commands: Commands,
     font_assets: Res<FontAssets>,
     button_colors: Res<ButtonColors>,
     sprite_assets: Res<SpriteAssets>,
+    transfer_status: Res<SaveTransferStatus>,
 ) {
     commands
         .spawn(ImageBundle {
@@ -251,15 +441,38 @@ fn setup_pause_menu(
         Vec2::new(10., 60.),
         Vec2::new(19., 8.),
     );
+    spawn_menu_button(
+        &mut commands,
+        &button_colors,
+        &font_assets.press_start,
+        MenuButton::ExportSave,
+        Vec2::new(30., 50.),
+        Vec2::new(19., 8.),
+    );
+    spawn_menu_button(
+        &mut commands,
+        &button_colors,
+        &font_assets.press_start,
+        MenuButton::ImportSave,
+        Vec2::new(30., 60.),
+        Vec2::new(19., 8.),
+    );
+    spawn_save_transfer_text(
+        &mut commands,
+        &font_assets.press_start,
+        &transfer_status.message,
+        Vec2::new(10., 70.),
+    );
 }
 
 fn setup_level_select(
     mut commands: Commands,
     font_assets: Res<FontAssets>,
     button_colors: Res<ButtonColors>,
-    data_store: Res<PkvStore>,
+    save_file: Res<SaveFile>,
+    level_graph: Res<LevelGraph>,
+    secret_index: Res<SecretIndex>,
 ) {
-    let level_sequence = [0, 1, 2, 3, 5, 4, 6, 7, 8, 9, 16, 12, 11, 10, 13, 14];
     let mut sequence_index = 0;
     let size = Vec2::new(16., 8.);
     let spacing = size + Vec2::new(2., 2.);
@@ -267,26 +480,188 @@ fn setup_level_select(
 
     let columns = 4;
 
-    for level_index in level_sequence {
+    for (level_number, level_index) in level_graph.order.iter().enumerate() {
+        let unlocked =
+            level_graph.is_unlocked(*level_index, |level| save_file.has_completed_level(level));
+
+        if !unlocked {
+            continue; // TODO - grey out or something, don't just hide
+        }
+
         let col = sequence_index % columns;
         let row = sequence_index / columns;
         let pos = base_pos + Vec2::new(spacing.x * (col as f32), spacing.y * (row as f32));
+        let secrets_found =
+            secret_index.found_percent(*level_index, |iid| save_file.has_found_secret(iid));
 
         spawn_level_select_button(
             &mut commands,
             &button_colors,
             &font_assets.press_start,
-            level_index,
-            sequence_index + 1,
+            *level_index,
+            level_number as i32 + 1,
+            secrets_found,
             pos,
             size,
         );
 
-        if !crate::world::SaveData::has_completed_level(&data_store, level_index) {
-            break; // TODO - grey out or something, don't just hide
+        sequence_index += 1;
+    }
+
+    spawn_menu_button(
+        &mut commands,
+        &button_colors,
+        &font_assets.press_start,
+        MenuButton::Menu,
+        Vec2::new(10., 80.),
+        Vec2::new(19., 8.),
+    );
+}
+
+fn setup_shop(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    state: Res<State<GameState>>,
+) {
+    for (index, upgrade) in Upgrade::ALL.into_iter().enumerate() {
+        spawn_menu_button(
+            &mut commands,
+            &button_colors,
+            &font_assets.press_start,
+            MenuButton::Buy(upgrade),
+            Vec2::new(30., 25. + 14. * index as f32),
+            Vec2::new(40., 10.),
+        );
+    }
+
+    // Opened between levels, the level is waiting underneath
+    let leave_button = if state.inactives().is_empty() {
+        MenuButton::Menu
+    } else {
+        MenuButton::Resume
+    };
+
+    spawn_menu_button(
+        &mut commands,
+        &button_colors,
+        &font_assets.press_start,
+        leave_button,
+        Vec2::new(10., 80.),
+        Vec2::new(19., 8.),
+    );
+
+    commands
+        .spawn(TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: font_assets.press_start.clone(),
+                    font_size: 24.,
+                    color: GRAIN_COLOR,
+                },
+            ),
+            style: Style {
+                position: UiRect {
+                    left: Val::Percent(30.),
+                    top: Val::Percent(10.),
+                    ..Default::default()
+                },
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(ShopGrainText)
+        .insert(MenuElement);
+}
+
+/// Keeps the prices and grain on show up to date as upgrades are bought
+fn update_shop_labels(
+    buttons: Query<(&MenuButton, &Children)>,
+    mut texts: Query<&mut Text, Without<ShopGrainText>>,
+    mut grain_text: Query<&mut Text, With<ShopGrainText>>,
+    granary: Res<Granary>,
+    save_file: Res<SaveFile>,
+) {
+    for (button, children) in &buttons {
+        let MenuButton::Buy(upgrade) = button else {
+            continue;
+        };
+
+        let level = upgrade.level(&save_file);
+        let label = match upgrade.price(level) {
+            Some(price) => format!("{} {}\n{}g", upgrade.label(), level + 1, price),
+            None => format!("{}\nMax", upgrade.label()),
+        };
+
+        // Greyed out when there isn't enough grain, or nothing left to buy
+        let color = if upgrade.affordable(granary.grain, &save_file) {
+            Color::rgb(0.9, 0.9, 0.9)
+        } else {
+            Color::rgb(0.35, 0.35, 0.35)
+        };
+
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                if text.sections[0].value != label {
+                    text.sections[0].value = label.clone();
+                }
+                if text.sections[0].style.color != color {
+                    text.sections[0].style.color = color;
+                }
+            }
         }
+    }
 
-        sequence_index += 1;
+    for mut text in &mut grain_text {
+        let label = format!("{} grain", granary.grain);
+        if text.sections[0].value != label {
+            text.sections[0].value = label;
+        }
+    }
+}
+
+fn setup_save_slots(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    mut slot_menu: ResMut<SlotMenu>,
+    data_store: Res<PkvStore>,
+    save_slot: Res<SaveSlot>,
+    save_file: Res<SaveFile>,
+) {
+    slot_menu.reload(&data_store, &save_slot, &save_file);
+    slot_menu.copying = None;
+    slot_menu.confirming = None;
+
+    for slot in 0..SaveFile::SLOTS {
+        let top = 20. + 18. * slot as f32;
+
+        spawn_menu_button(
+            &mut commands,
+            &button_colors,
+            &font_assets.press_start,
+            MenuButton::Slot(slot),
+            Vec2::new(15., top),
+            Vec2::new(40., 14.),
+        );
+        spawn_menu_button(
+            &mut commands,
+            &button_colors,
+            &font_assets.press_start,
+            MenuButton::CopySlot(slot),
+            Vec2::new(57., top),
+            Vec2::new(13., 14.),
+        );
+        spawn_menu_button(
+            &mut commands,
+            &button_colors,
+            &font_assets.press_start,
+            MenuButton::DeleteSlot(slot),
+            Vec2::new(72., top),
+            Vec2::new(13., 14.),
+        );
     }
 
     spawn_menu_button(
@@ -299,12 +674,84 @@ fn setup_level_select(
     );
 }
 
+/// Shows each slot's progress, and what pressing a button will do while copying or deleting
+fn update_slot_labels(
+    buttons: Query<(&MenuButton, &Children)>,
+    mut texts: Query<&mut Text>,
+    slot_menu: Res<SlotMenu>,
+    save_slot: Res<SaveSlot>,
+    level_graph: Res<LevelGraph>,
+) {
+    for (button, children) in &buttons {
+        let Some(label) = slot_menu.label(button, &save_slot, level_graph.order.len()) else {
+            continue;
+        };
+
+        for child in children.iter() {
+            if let Ok(mut text) = texts.get_mut(*child) {
+                if text.sections[0].value != label {
+                    text.sections[0].value = label.clone();
+                }
+            }
+        }
+    }
+}
+
+fn spawn_save_transfer_text(
+    commands: &mut Commands,
+    font: &Handle<Font>,
+    message: &str,
+    position: Vec2,
+) {
+    commands
+        .spawn(TextBundle {
+            text: Text::from_section(
+                message,
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+            ),
+            style: Style {
+                position: UiRect {
+                    left: Val::Percent(position.x),
+                    top: Val::Percent(position.y),
+                    ..Default::default()
+                },
+                position_type: PositionType::Absolute,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(SaveTransferText)
+        .insert(MenuElement);
+}
+
+fn update_save_transfer_text(
+    transfer_status: Res<SaveTransferStatus>,
+    mut transfer_text: Query<&mut Text, With<SaveTransferText>>,
+) {
+    if transfer_status.is_changed() {
+        for mut text in &mut transfer_text {
+            text.sections[0].value = transfer_status.message.clone();
+        }
+    }
+}
+
+/// Messages last until the menu they're shown in is left, an import from the pause menu
+/// carries its message to the main menu it returns to
+fn clear_save_transfer_status(mut transfer_status: ResMut<SaveTransferStatus>) {
+    transfer_status.message.clear();
+}
+
 fn spawn_level_select_button(
     commands: &mut Commands,
     button_colors: &ButtonColors,
     font: &Handle<Font>,
     true_level_index: usize,
     level_number: i32,
+    secrets_found: Option<usize>,
     position: Vec2,
     size: Vec2,
 ) {
@@ -314,7 +761,11 @@ fn spawn_level_select_button(
         ..Default::default()
     };
 
-    let label_string = "Level ".to_string() + level_number.to_string().as_str();
+    let mut label_string = "Level ".to_string() + level_number.to_string().as_str();
+
+    if let Some(percent) = secrets_found {
+        label_string += format!("\n{}%", percent).as_str();
+    }
 
     commands
         .spawn(ButtonBundle {
@@ -375,6 +826,13 @@ fn spawn_menu_button(
             }
         }
         MenuButton::LevelSelect => "Level\nSelect",
+        MenuButton::Shop => "Shop",
+        MenuButton::Buy(upgrade) => upgrade.label(),
+        MenuButton::SaveSlots => "Save\nSlots",
+        MenuButton::ExportSave => "Export\nSave",
+        MenuButton::ImportSave => "Import\nSave",
+        // Filled in by `update_slot_labels`
+        MenuButton::Slot(_) | MenuButton::CopySlot(_) | MenuButton::DeleteSlot(_) => "",
         MenuButton::Options => "Options",
         MenuButton::Menu => "Main Menu",
         MenuButton::Resume => "Resume",
@@ -511,7 +969,11 @@ fn button_nav_events(
     mut state: ResMut<State<GameState>>,
     mut exit: EventWriter<AppExit>,
     mut level_selection: ResMut<LevelSelection>,
+    mut level_entry: ResMut<crate::door::LevelEntry>,
     mut reload_event_writer: EventWriter<crate::world::ReloadWorldEvent>,
+    mut granary: ResMut<Granary>,
+    mut save_file: ResMut<SaveFile>,
+    mut transfer_status: ResMut<SaveTransferStatus>,
 ) {
     for event in events.iter() {
         match event {
@@ -522,8 +984,48 @@ fn button_nav_events(
                             MenuButton::Play(level_index) => {
                                 state.replace(GameState::Playing).unwrap();
                                 *level_selection = LevelSelection::Index(*level_index);
+                                level_entry.door = None;
                             }
                             MenuButton::LevelSelect => state.set(GameState::LevelSelect).unwrap(),
+                            MenuButton::Shop => state.set(GameState::Shop).unwrap(),
+                            MenuButton::Buy(upgrade) => {
+                                if !upgrade.buy(&mut granary, &mut save_file) {
+                                    println!("can't afford {}", upgrade.name());
+                                }
+                            }
+                            MenuButton::SaveSlots => state.set(GameState::SaveSlots).unwrap(),
+                            // Handled by `slot_nav_events`
+                            MenuButton::Slot(_)
+                            | MenuButton::CopySlot(_)
+                            | MenuButton::DeleteSlot(_) => (),
+                            MenuButton::ExportSave => {
+                                transfer_status.message =
+                                    match save_file.export(SaveFile::EXPORT_PATH) {
+                                        Ok(()) => format!("Saved to {}", SaveFile::EXPORT_PATH),
+                                        Err(error) => {
+                                            println!("{}", error);
+                                            error
+                                        }
+                                    };
+                            }
+                            MenuButton::ImportSave => {
+                                transfer_status.message =
+                                    match SaveFile::import(SaveFile::EXPORT_PATH) {
+                                        Ok(imported) => {
+                                            *save_file = imported;
+                                            // The paused level was built from the old save, so
+                                            // leave it rather than play on with stale progress
+                                            if *state.current() == GameState::Paused {
+                                                state.replace(GameState::Menu).unwrap();
+                                            }
+                                            format!("Loaded {}", SaveFile::EXPORT_PATH)
+                                        }
+                                        Err(error) => {
+                                            println!("{}", error);
+                                            error
+                                        }
+                                    };
+                            }
                             MenuButton::Options => (),
                             MenuButton::Menu => state.replace(GameState::Menu).unwrap(),
                             MenuButton::Resume => state.pop().unwrap(),
@@ -541,8 +1043,188 @@ fn button_nav_events(
     }
 }
 
+fn slot_nav_events(
+    mut events: EventReader<NavEvent>,
+    buttons: Query<&MenuButton>,
+    mut state: ResMut<State<GameState>>,
+    mut slot_menu: ResMut<SlotMenu>,
+    mut data_store: ResMut<PkvStore>,
+    mut save_slot: ResMut<SaveSlot>,
+    mut save_file: ResMut<SaveFile>,
+) {
+    for event in events.iter() {
+        let NavEvent::NoChanges {
+            from,
+            request: NavRequest::Action,
+        } = event
+        else {
+            continue;
+        };
+
+        let Ok(button) = buttons.get(*from.first()) else {
+            continue;
+        };
+
+        match *button {
+            MenuButton::Slot(slot) => match slot_menu.copying {
+                Some(from) if from != slot => {
+                    let action = SlotAction::Copy { from, to: slot };
+
+                    if slot_menu.slots[slot].is_empty() || slot_menu.confirming == Some(action) {
+                        // Copies are written straight to the slot, so a read only one is
+                        // replaced, but a read only save is never copied anywhere
+                        let copy = slot_menu.slots[from].clone();
+                        if copy.store(&mut data_store, slot) {
+                            println!("copied save slot {} to {}", from, slot);
+                            if slot == save_slot.index {
+                                *save_file = copy;
+                            }
+                        }
+                        slot_menu.copying = None;
+                        slot_menu.confirming = None;
+                    } else {
+                        slot_menu.confirming = Some(action);
+                    }
+                }
+                // Picking the slot being copied again cancels the copy
+                Some(_) => {
+                    slot_menu.copying = None;
+                    slot_menu.confirming = None;
+                }
+                None => {
+                    println!("switched to save slot {}", slot);
+                    save_slot.index = slot;
+                    *save_file = slot_menu.slots[slot].clone();
+                    state.replace(GameState::Menu).unwrap();
+                }
+            },
+            MenuButton::CopySlot(slot) => {
+                slot_menu.confirming = None;
+                let source = &slot_menu.slots[slot];
+                let can_copy = !source.is_empty() && !source.read_only;
+                slot_menu.copying = if slot_menu.copying == Some(slot) || !can_copy {
+                    None
+                } else {
+                    Some(slot)
+                };
+            }
+            MenuButton::DeleteSlot(slot) => {
+                slot_menu.copying = None;
+
+                if slot_menu.confirming == Some(SlotAction::Delete(slot)) {
+                    SaveFile::delete(&mut data_store, slot);
+                    if slot == save_slot.index {
+                        *save_file = SaveFile::default();
+                    }
+                    slot_menu.confirming = None;
+                } else {
+                    slot_menu.confirming = Some(SlotAction::Delete(slot));
+                }
+            }
+            _ => continue,
+        }
+
+        slot_menu.reload(&data_store, &save_slot, &save_file);
+    }
+}
+
 fn cleanup_menu(mut commands: Commands, buttons: Query<Entity, With<MenuElement>>) {
     for button in buttons.iter() {
         commands.entity(button).despawn_recursive();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot_menu() -> SlotMenu {
+        let mut played = SaveFile::default();
+        played.completed_levels.extend([0, 1]);
+        played.play_time = 3725.;
+
+        SlotMenu {
+            slots: vec![played, SaveFile::default(), SaveFile::default()],
+            ..Default::default()
+        }
+    }
+
+    fn label(slot_menu: &SlotMenu, button: MenuButton) -> Option<String> {
+        slot_menu.label(&button, &SaveSlot { index: 0 }, 4)
+    }
+
+    #[test]
+    fn slot_summaries() {
+        let slot_menu = slot_menu();
+
+        assert_eq!(
+            label(&slot_menu, MenuButton::Slot(0)).unwrap(),
+            "> Slot 1 <\n50% 1:02:05"
+        );
+        assert_eq!(
+            label(&slot_menu, MenuButton::Slot(1)).unwrap(),
+            "Slot 2\nEmpty"
+        );
+        assert_eq!(label(&slot_menu, MenuButton::Slot(3)), None);
+        assert_eq!(label(&slot_menu, MenuButton::Menu), None);
+    }
+
+    #[test]
+    fn read_only_slots() {
+        let mut slot_menu = slot_menu();
+        slot_menu.slots[2].read_only = true;
+
+        assert_eq!(
+            label(&slot_menu, MenuButton::Slot(2)).unwrap(),
+            "Slot 3\nRead only"
+        );
+    }
+
+    #[test]
+    fn copying() {
+        let mut slot_menu = slot_menu();
+        slot_menu.copying = Some(0);
+
+        assert_eq!(
+            label(&slot_menu, MenuButton::Slot(0)).unwrap(),
+            "> Slot 1 <\n50% 1:02:05"
+        );
+        assert_eq!(
+            label(&slot_menu, MenuButton::Slot(1)).unwrap(),
+            "Copy to\nSlot 2"
+        );
+        assert_eq!(
+            label(&slot_menu, MenuButton::CopySlot(0)).unwrap(),
+            "Cancel"
+        );
+        assert_eq!(label(&slot_menu, MenuButton::CopySlot(1)).unwrap(), "Copy");
+    }
+
+    #[test]
+    fn confirming() {
+        let mut slot_menu = slot_menu();
+        slot_menu.copying = Some(0);
+        slot_menu.confirming = Some(SlotAction::Copy { from: 0, to: 2 });
+
+        assert_eq!(
+            label(&slot_menu, MenuButton::Slot(2)).unwrap(),
+            "Overwrite\nSlot 3?"
+        );
+        assert_eq!(
+            label(&slot_menu, MenuButton::Slot(1)).unwrap(),
+            "Copy to\nSlot 2"
+        );
+
+        slot_menu.copying = None;
+        slot_menu.confirming = Some(SlotAction::Delete(1));
+
+        assert_eq!(
+            label(&slot_menu, MenuButton::DeleteSlot(1)).unwrap(),
+            "Sure?"
+        );
+        assert_eq!(
+            label(&slot_menu, MenuButton::DeleteSlot(0)).unwrap(),
+            "Delete"
+        );
+    }
+}
@@ -1,20 +1,70 @@
 use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::*;
+use std::collections::HashSet;
 
+use crate::loading::AudioAssets;
+use crate::soul::SoulTier;
+use crate::sprite_anim::SpriteAnimator;
+use crate::world::Labeled;
 use crate::GameState;
 
 pub struct PickupPlugin;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum PickupType {
-    Soul,
+    Soul {
+        tier: SoulTier,
+        value: usize,
+        group: Option<String>,
+    },
+    Key { id: String },
+    Health { amount: usize },
+    Secret { iid: String },
+    Grain { amount: usize },
+}
+
+impl PickupType {
+    pub fn effect_color(&self) -> Color {
+        match self {
+            PickupType::Soul { tier, .. } => tier.color(),
+            PickupType::Key { id } => crate::gate::key_color(id),
+            PickupType::Health { .. } => Color::rgb(0.9, 0.35, 0.35),
+            PickupType::Secret { .. } => Color::rgb(0.35, 0.68, 0.75),
+            PickupType::Grain { .. } => crate::harvest::GRAIN_COLOR,
+        }
+    }
 }
 
 #[derive(Component, Clone, Default)]
 pub struct Pickup {
     pub pickup_type: Option<PickupType>,
+    /// Seconds until this can be collected, so freshly spawned pickups aren't grabbed instantly
+    pub delay: f32,
 }
 
+impl Pickup {
+    pub fn new(pickup_type: PickupType) -> Self {
+        Pickup {
+            pickup_type: Some(pickup_type),
+            delay: 0.,
+        }
+    }
+
+    pub fn with_delay(mut self, delay: f32) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    pub fn collectable(&self) -> bool {
+        self.pickup_type.is_some() && self.delay <= 0.
+    }
+}
+
+/// Collect pickups within this radius instead of using the collector's own collider
+#[derive(Component, Clone, Copy, Debug)]
+pub struct PickupRadius(pub f32);
+
 #[derive(Component, Default, Clone)]
 pub struct PickupCollector;
 
@@ -25,41 +75,245 @@ pub struct PickupEvent {
     pub collector_entity: Entity,
 }
 
+pub struct CollectedKeyEvent {
+    pub collector_entity: Entity,
+    pub pickup_pos: Vec3,
+    pub id: String,
+}
+
+pub struct CollectedHealthEvent {
+    pub collector_entity: Entity,
+    pub pickup_pos: Vec3,
+    pub amount: usize,
+}
+
+pub struct CollectedSecretEvent {
+    pub collector_entity: Entity,
+    pub pickup_pos: Vec3,
+    pub iid: String,
+}
+
+pub struct CollectedGrainEvent {
+    pub collector_entity: Entity,
+    pub pickup_pos: Vec3,
+    pub amount: usize,
+}
+
+/// Generic LDtk pickup for everything that isn't a soul
+#[derive(Clone, Default, Bundle)]
+pub struct PickupBundle {
+    #[bundle]
+    pub sprite_sheet_bundle: SpriteSheetBundle,
+    pub sprite_animator: SpriteAnimator,
+    pub collider: Collider,
+    pub sensor: Sensor,
+    pub label: Labeled,
+    pub pickup: Pickup,
+}
+
 impl Plugin for PickupPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<PickupEvent>().add_system_set(
-            SystemSet::on_update(GameState::Playing).with_system(check_for_pickups),
-        );
+        app.add_event::<PickupEvent>()
+            .add_event::<CollectedKeyEvent>()
+            .add_event::<CollectedHealthEvent>()
+            .add_event::<CollectedSecretEvent>()
+            .add_event::<CollectedGrainEvent>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(check_for_pickups),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(item_pickups)
+                    .after(check_for_pickups),
+            );
+    }
+}
+
+impl LdtkEntity for PickupBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        _layer_instance: &LayerInstance,
+        _tileset: Option<&Handle<Image>>,
+        _tileset_definition: Option<&TilesetDefinition>,
+        asset_server: &AssetServer,
+        texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Self {
+        let mut pickup_type = match entity_instance.identifier.as_str() {
+            "Key" => PickupType::Key {
+                id: String::new(),
+            },
+            "Health" => PickupType::Health { amount: 1 },
+            "Secret" => PickupType::Secret {
+                iid: entity_instance.iid.clone(),
+            },
+            unknown => panic!("LDtk entity \"{}\" is not a pickup!", unknown),
+        };
+
+        for field in entity_instance.field_instances.iter() {
+            match (field.identifier.as_str(), &mut pickup_type) {
+                ("Amount", PickupType::Health { amount }) => {
+                    if let FieldValue::Int(Some(value)) = field.value {
+                        *amount = value.max(0) as usize;
+                    }
+                }
+                ("KeyId", PickupType::Key { id }) => {
+                    if let FieldValue::String(Some(value)) = &field.value {
+                        *id = value.to_lowercase();
+                    }
+                }
+                (unknown, _) => println!(
+                    "Unknown field \"{}\" on LDtk {} object!",
+                    unknown,
+                    entity_instance.identifier.to_lowercase()
+                ),
+            }
+        }
+
+        PickupBundle::new(
+            pickup_type,
+            entity_instance.identifier.to_lowercase(),
+            asset_server,
+            texture_atlases,
+        )
+    }
+}
+
+impl PickupBundle {
+    pub fn new(
+        pickup_type: PickupType,
+        name: String,
+        asset_server: &AssetServer,
+        texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Self {
+        // No dedicated art for these yet, so tint the soul sprite
+        let texture_handle = asset_server.load("sprites/soul.png");
+        let texture_atlas =
+            TextureAtlas::from_grid(texture_handle, Vec2::new(20., 20.), 4, 1, None, None);
+        let texture_atlas_handle = texture_atlases.add(texture_atlas);
+
+        PickupBundle {
+            sprite_sheet_bundle: SpriteSheetBundle {
+                texture_atlas: texture_atlas_handle,
+                sprite: TextureAtlasSprite {
+                    color: pickup_type.effect_color(),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
+                ..Default::default()
+            },
+            sprite_animator: SpriteAnimator::new(0, 3, 4, 0.2, true, true),
+            collider: Collider::ball(6.),
+            sensor: Sensor,
+            label: Labeled { name },
+            pickup: Pickup::new(pickup_type),
+        }
     }
 }
 
 pub fn check_for_pickups(
+    time: Res<Time>,
     mut pickup_writer: EventWriter<PickupEvent>,
     rapier_context: Res<RapierContext>,
-    collector_query: Query<(Entity, &Transform), With<PickupCollector>>,
-    pickup_query: Query<(&Pickup, &Transform)>,
+    collector_query: Query<
+        (Entity, &GlobalTransform, Option<&Collider>, Option<&PickupRadius>),
+        With<PickupCollector>,
+    >,
+    mut pickup_query: Query<(&mut Pickup, &GlobalTransform)>,
     mut commands: Commands,
 ) {
-    for (collector_entity, transform) in collector_query.iter() {
-        let shape = Collider::capsule_y(5.5, 5.5);
-        let filter = QueryFilter::new();
-        let shape_pos = transform.translation.truncate();
-
-        rapier_context.intersections_with_shape(shape_pos, 0., &shape, filter, |entity| -> bool {
-            if let Ok((pickup, pickup_transform)) = pickup_query.get(entity) {
-                if let Some(pickup_type) = &pickup.pickup_type {
-                    pickup_writer.send(PickupEvent {
-                        pickup_entity: entity,
-                        pickup_type: pickup_type.clone(),
-                        pickup_position: pickup_transform.translation,
-                        collector_entity,
-                    });
+    for (mut pickup, _) in &mut pickup_query {
+        if pickup.delay > 0. {
+            pickup.delay -= time.delta_seconds();
+        }
+    }
+
+    // Stops two collectors touching the same pickup on one frame from both getting it
+    let mut collected = HashSet::new();
+
+    for (collector_entity, transform, collider, pickup_radius) in collector_query.iter() {
+        let shape = if let Some(PickupRadius(radius)) = pickup_radius {
+            Collider::ball(*radius)
+        } else if let Some(collider) = collider {
+            collider.clone()
+        } else {
+            continue;
+        };
+        let filter = QueryFilter::new().exclude_collider(collector_entity);
+        let (_, rotation, translation) = transform.to_scale_rotation_translation();
+        let shape_pos = translation.truncate();
+        let shape_rot = rotation.to_euler(EulerRot::XYZ).2;
+
+        rapier_context.intersections_with_shape(
+            shape_pos,
+            shape_rot,
+            &shape,
+            filter,
+            |entity| -> bool {
+                if let Ok((pickup, pickup_transform)) = pickup_query.get(entity) {
+                    if let Some(pickup_type) = &pickup.pickup_type {
+                        if pickup.collectable() && collected.insert(entity) {
+                            pickup_writer.send(PickupEvent {
+                                pickup_entity: entity,
+                                pickup_type: pickup_type.clone(),
+                                pickup_position: pickup_transform.translation(),
+                                collector_entity,
+                            });
+
+                            commands.entity(entity).despawn_recursive();
+                        }
+                    }
                 }
 
-                commands.entity(entity).despawn_recursive();
-            }
+                true
+            },
+        );
+    }
+}
 
-            true
-        });
+fn item_pickups(
+    mut pickup_reader: EventReader<PickupEvent>,
+    mut key_writer: EventWriter<CollectedKeyEvent>,
+    mut health_writer: EventWriter<CollectedHealthEvent>,
+    mut secret_writer: EventWriter<CollectedSecretEvent>,
+    mut grain_writer: EventWriter<CollectedGrainEvent>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    for pickup_ev in pickup_reader.iter() {
+        match &pickup_ev.pickup_type {
+            PickupType::Soul { .. } => (), // Handled by the soul plugin
+            PickupType::Key { id } => {
+                key_writer.send(CollectedKeyEvent {
+                    collector_entity: pickup_ev.collector_entity,
+                    pickup_pos: pickup_ev.pickup_position,
+                    id: id.clone(),
+                });
+                audio.play(audio_assets.key_pickup.clone());
+            }
+            PickupType::Health { amount } => {
+                health_writer.send(CollectedHealthEvent {
+                    collector_entity: pickup_ev.collector_entity,
+                    pickup_pos: pickup_ev.pickup_position,
+                    amount: *amount,
+                });
+                audio.play(audio_assets.health_pickup.clone());
+            }
+            PickupType::Secret { iid } => {
+                secret_writer.send(CollectedSecretEvent {
+                    collector_entity: pickup_ev.collector_entity,
+                    pickup_pos: pickup_ev.pickup_position,
+                    iid: iid.clone(),
+                });
+                audio.play(audio_assets.secret_pickup.clone());
+            }
+            PickupType::Grain { amount } => {
+                grain_writer.send(CollectedGrainEvent {
+                    collector_entity: pickup_ev.collector_entity,
+                    pickup_pos: pickup_ev.pickup_position,
+                    amount: *amount,
+                });
+                audio.play(audio_assets.pickup.clone());
+            }
+        }
     }
 }
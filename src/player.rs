@@ -1,8 +1,15 @@
 use crate::actions::Actions;
 use crate::actor::*;
-use crate::door::Door;
+use crate::checkpoint::PlayerDiedEvent;
+use crate::door::{Door, DoorMode, DoorOpenedEvent};
+use crate::gate::KeyRing;
+use crate::harvest::Granary;
+use crate::magnet::SoulMagnet;
+use crate::pickup::CollectedHealthEvent;
+use crate::soul::SoulWallet;
 use crate::sprite_anim::SpriteAnimator;
-use crate::world::{ChangeLevelEvent, Labeled, ReloadWorldEvent};
+use crate::world::{ChangeLevelEvent, Labeled, Surface};
+use crate::zone::ZoneEffects;
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
@@ -16,6 +23,29 @@ pub struct Player;
 #[derive(Component, Debug, Default, Clone)]
 pub struct TouchDeath;
 
+/// Hits from deadly things the player can shrug off, filled up by health pickups
+#[derive(Component, Debug, Default, Clone)]
+pub struct Health {
+    pub spare: usize,
+    /// Seconds until the next hit counts, so one spike doesn't take every spare at once
+    pub invulnerable: f32,
+}
+
+impl Health {
+    pub const INVULNERABLE_TIME: f32 = 1.;
+
+    /// Spends a spare hit if there is one, returning whether the player survives
+    pub fn take_hit(&mut self) -> bool {
+        if self.spare == 0 {
+            return false;
+        }
+
+        self.spare -= 1;
+        self.invulnerable = Health::INVULNERABLE_TIME;
+        true
+    }
+}
+
 /// This plugin handles player related stuff like movement
 /// Player logic is only active during the State `GameState::Playing`
 impl Plugin for PlayerPlugin {
@@ -27,7 +57,12 @@ impl Plugin for PlayerPlugin {
                 .after(crate::actions::set_movement_actions)
                 .before(actor_movement),
         )
-        .add_system_set(SystemSet::on_update(GameState::Playing).with_system(player_death))
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(collect_health)
+                .with_system(tick_health)
+                .with_system(player_death.after(collect_health).after(tick_health)),
+        )
         .add_system_set(SystemSet::on_update(GameState::Playing).with_system(player_win));
     }
 }
@@ -50,6 +85,12 @@ pub struct PlayerBundle {
     pub actor_effects: ActorEffects,
     pub pickup_collector: crate::pickup::PickupCollector,
     pub squashy: Squashy,
+    pub soul_magnet: SoulMagnet,
+    pub soul_wallet: SoulWallet,
+    pub key_ring: KeyRing,
+    pub health: Health,
+    /// Lives outside its level, so it survives walking into the neighbouring ones
+    pub worldly: Worldly,
 }
 
 impl LdtkEntity for PlayerBundle {
@@ -82,6 +123,7 @@ impl LdtkEntity for PlayerBundle {
         let pickup_atlas_handle = texture_atlases.add(pickup_texture_atlas);
 
         let mut actor = Actor::default();
+        let mut soul_magnet = SoulMagnet::default();
 
         actor.can_attack = true;
         actor.attack_sprite = Some(scythe_atlas_handle);
@@ -143,6 +185,16 @@ impl LdtkEntity for PlayerBundle {
                         actor.attack_range = value;
                     }
                 }
+                "SoulMagnet" => {
+                    if let FieldValue::Bool(value) = field.value {
+                        soul_magnet.unlocked = value;
+                    }
+                }
+                "MagnetRadius" => {
+                    if let FieldValue::Float(Some(value)) = field.value {
+                        soul_magnet.radius = value;
+                    }
+                }
                 unknown => println!("Unknown field \"{}\" on LDtk player object!", unknown),
             }
         }
@@ -173,12 +225,15 @@ impl LdtkEntity for PlayerBundle {
                 facing_left: false,
                 velocity: Vec2::ZERO,
                 air_timer: 0.,
+                air_jumps_used: 0,
                 attack_direction: None,
                 attack_timer: 0.,
                 left_wall: false,
                 right_wall: false,
                 event: None,
                 last_dt: 1.,
+                surface: Surface::Normal,
+                zone: ZoneEffects::default(),
             },
             actor_anim: ActorAnimationStates {
                 idle_row: 0,
@@ -186,10 +241,13 @@ impl LdtkEntity for PlayerBundle {
                 jump_row: 2,
                 fall_row: 3,
                 attack_row: 4,
+                // No swimming frames yet, paddling reuses the run cycle
+                swim_row: 1,
             },
             actor_audio: ActorAudio {
                 jump: asset_server.load("audio/jump3.ogg"),
                 land: asset_server.load("audio/land2.ogg"),
+                bounce: asset_server.load("audio/jump2.ogg"),
                 attack: asset_server.load("audio/attack1.ogg"),
                 hit: asset_server.load("audio/hit.ogg"),
                 death: asset_server.load("audio/death1.ogg"),
@@ -213,19 +271,39 @@ impl LdtkEntity for PlayerBundle {
                 state_time: 0.,
                 from_pos: Vec2::ONE,
             },
+            soul_magnet,
+            soul_wallet: SoulWallet::default(),
+            key_ring: KeyRing::default(),
+            health: Health::default(),
+            worldly: Worldly {
+                entity_iid: entity_instance.iid.clone(),
+            },
         }
     }
 }
 
-fn player_inputs(
+pub fn player_inputs(
     actions: Res<Actions>,
-    mut player_query: Query<(&mut Actor, &ActorStatus), With<Player>>,
+    mut player_query: Query<(&mut Actor, &mut ActorStatus), With<Player>>,
 ) {
     let input: Vec2 = Vec2::new(actions.player_movement.x, actions.player_movement.y);
-    for (mut actor, status) in &mut player_query {
+    for (mut actor, mut status) in &mut player_query {
+        let jump_pressed = actions.jump && !actor.jump_input;
         actor.jump_input = actions.jump;
         actor.can_jump = status.grounded || status.air_timer < actor.jump_time;
 
+        // An air jump starts a fresh jump window, so it can be held like one off the ground
+        if !actor.can_jump
+            && jump_pressed
+            && status.zone.water.is_none()
+            && status.air_jumps_used < actor.air_jumps
+        {
+            status.air_jumps_used += 1;
+            status.air_timer = 0.;
+            status.event = Some(ActorEvent::Launched);
+            actor.can_jump = true;
+        }
+
         if actions.attack && status.attack_direction.is_none() {
             actor.attack_input = Some(input);
         }
@@ -237,55 +315,148 @@ fn player_inputs(
     }
 }
 
-fn player_win(
+pub fn player_win(
     mut next_level_writer: EventWriter<ChangeLevelEvent>,
+    mut door_opened_writer: EventWriter<DoorOpenedEvent>,
     rapier_context: Res<RapierContext>,
-    mut doors: Query<&mut Door>,
-    mut player_query: Query<(&Transform, &mut ActorStatus), With<Player>>,
+    mut doors: Query<(Entity, &mut Door)>,
+    mut player_query: Query<(&GlobalTransform, &mut ActorStatus, &mut SoulWallet), With<Player>>,
+    mut granary: ResMut<Granary>,
+    actions: Res<Actions>,
 ) {
-    for (transform, mut status) in &mut player_query {
+    for (transform, mut status, mut wallet) in &mut player_query {
         let shape = Collider::capsule_y(5.5, 5.5);
         let filter = QueryFilter::new();
-        let shape_pos = transform.translation.truncate();
+        let shape_pos = transform.translation().truncate();
+        let mut touching = Vec::new();
 
         rapier_context.intersections_with_shape(shape_pos, 0., &shape, filter, |entity| -> bool {
-            if let Ok(mut door) = doors.get_mut(entity) {
-                if door.required_souls == 0 {
+            if let Ok((_, mut door)) = doors.get_mut(entity) {
+                touching.push(entity);
+
+                if !door.open {
+                    let cost = door.required_souls;
+
+                    // Grain only makes up for missing souls, and only when the player asks
+                    let bought = if door.mode == DoorMode::Consume
+                        && wallet.spend(cost, door.soul_group.as_deref())
+                    {
+                        true
+                    } else if let Some(price) = door.grain_price {
+                        actions.interact
+                            && door.souls_remaining(&wallet) > 0
+                            && granary.spend(price)
+                    } else {
+                        false
+                    };
+
+                    if bought {
+                        door.open = true;
+                        door_opened_writer.send(DoorOpenedEvent {
+                            door_entity: entity,
+                        });
+                    }
+                } else if !door.disarmed {
                     next_level_writer.send(ChangeLevelEvent {
-                        index: door.next_level,
+                        level: door.target_level.clone(),
+                        entry_door: door.entry_door.clone(),
                         completed: true,
-                        win_game: door.next_level == 32767,
+                        win_game: door.win_game,
                     });
                     status.event = Some(ActorEvent::Win);
 
-                    door.required_souls = usize::MAX;
+                    // Stops the door firing again before the level changes
+                    door.disarmed = true;
 
                     return false; // no need to keep looking
                 }
             }
             true
         });
+
+        // Doors the player arrived through only work again once they have stepped out
+        for (entity, mut door) in &mut doors {
+            if door.disarmed && !touching.contains(&entity) {
+                door.disarmed = false;
+            }
+        }
+    }
+}
+
+fn collect_health(
+    mut health_events: EventReader<CollectedHealthEvent>,
+    mut health_query: Query<&mut Health>,
+) {
+    for ev in health_events.iter() {
+        if let Ok(mut health) = health_query.get_mut(ev.collector_entity) {
+            health.spare += ev.amount;
+        }
+    }
+}
+
+fn tick_health(time: Res<Time>, mut health_query: Query<&mut Health>) {
+    for mut health in &mut health_query {
+        if health.invulnerable > 0. {
+            health.invulnerable = (health.invulnerable - time.delta_seconds()).max(0.);
+        }
     }
 }
 
-fn player_death(
-    mut player_query: Query<(&Transform, &mut ActorStatus), With<Player>>,
+pub fn player_death(
+    mut player_query: Query<(&GlobalTransform, &mut ActorStatus, &mut Health), With<Player>>,
     enemies_query: Query<Entity, With<TouchDeath>>,
-    mut reload_writer: EventWriter<ReloadWorldEvent>,
+    mut died_writer: EventWriter<PlayerDiedEvent>,
     rapier_context: Res<RapierContext>,
 ) {
-    for (transform, mut status) in &mut player_query {
+    for (transform, mut status, mut health) in &mut player_query {
+        if health.invulnerable > 0. {
+            continue;
+        }
+
         let shape = Collider::capsule_y(5.5, 5.5);
         let filter = QueryFilter::new();
-        let shape_pos = transform.translation.truncate();
+        let shape_pos = transform.translation().truncate();
 
         rapier_context.intersections_with_shape(shape_pos, 0., &shape, filter, |entity| -> bool {
             if let Ok(_touched_ent) = enemies_query.get(entity) {
-                reload_writer.send(ReloadWorldEvent);
-                status.event = Some(ActorEvent::Died);
+                if health.take_hit() {
+                    status.event = Some(ActorEvent::Hit);
+                } else {
+                    died_writer.send(PlayerDiedEvent);
+                    status.event = Some(ActorEvent::Died);
+                }
                 return false; // no need to keep looking
             }
             true
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_spare_health_dies() {
+        let mut health = Health::default();
+
+        assert!(!health.take_hit());
+        assert_eq!(health.invulnerable, 0.);
+    }
+
+    #[test]
+    fn spare_health_is_spent_on_hits() {
+        let mut health = Health {
+            spare: 2,
+            ..Default::default()
+        };
+
+        assert!(health.take_hit());
+        assert_eq!(health.spare, 1);
+        assert_eq!(health.invulnerable, Health::INVULNERABLE_TIME);
+
+        assert!(health.take_hit());
+        assert!(!health.take_hit());
+        assert_eq!(health.spare, 0);
+    }
+}
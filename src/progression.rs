@@ -0,0 +1,150 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::LdtkJson;
+use bevy_ecs_ldtk::prelude::*;
+use std::collections::VecDeque;
+
+use crate::door::level_doors;
+
+pub struct ProgressionPlugin;
+
+/// How levels lead into each other, worked out from the door links in the LDtk project
+#[derive(Resource, Default, Debug, Clone)]
+pub struct LevelGraph {
+    /// Level indices in the order the player reaches them, starting with the first level
+    pub order: Vec<usize>,
+    /// Levels each level's doors lead to, indexed like the project's levels
+    pub links: Vec<Vec<usize>>,
+}
+
+impl LevelGraph {
+    pub fn from_project(project: &LdtkJson) -> Self {
+        let links: Vec<Vec<usize>> = project
+            .levels
+            .iter()
+            .map(|level| {
+                let mut targets = Vec::new();
+
+                for door in level_doors(level) {
+                    let target = door
+                        .target_level
+                        .as_deref()
+                        .and_then(|target| crate::world::find_level_index(project, target));
+
+                    if let Some(target) = target {
+                        if !targets.contains(&target) {
+                            targets.push(target);
+                        }
+                    }
+                }
+
+                targets
+            })
+            .collect();
+
+        // Walk outwards from the first level, so branches come right after the level they split from
+        let mut order = Vec::new();
+        let mut queue = VecDeque::new();
+
+        if !links.is_empty() {
+            queue.push_back(0);
+        }
+
+        while let Some(index) = queue.pop_front() {
+            if order.contains(&index) {
+                continue;
+            }
+
+            order.push(index);
+            queue.extend(links[index].iter().copied());
+        }
+
+        LevelGraph { order, links }
+    }
+
+    /// Levels with a door into the given level
+    pub fn previous_levels(&self, level: usize) -> impl Iterator<Item = usize> + '_ {
+        self.links
+            .iter()
+            .enumerate()
+            .filter(move |(_, targets)| targets.contains(&level))
+            .map(|(index, _)| index)
+    }
+
+    pub fn next_levels(&self, level: usize) -> &[usize] {
+        self.links.get(level).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The first level is always open, the rest once a level leading into them is complete
+    pub fn is_unlocked(&self, level: usize, has_completed: impl Fn(usize) -> bool) -> bool {
+        self.order.first() == Some(&level) || self.previous_levels(level).any(has_completed)
+    }
+
+    /// Where to carry on from, the first level if it isn't done yet and otherwise the first
+    /// unfinished level a completed one leads to
+    pub fn next_level(&self, has_completed: impl Fn(usize) -> bool) -> Option<usize> {
+        let first = self.order.first().copied();
+
+        first.filter(|level| !has_completed(*level)).or_else(|| {
+            self.order
+                .iter()
+                .filter(|level| has_completed(**level))
+                .flat_map(|level| self.next_levels(*level))
+                .copied()
+                .find(|level| !has_completed(*level))
+        })
+    }
+}
+
+impl Plugin for ProgressionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LevelGraph>()
+            .add_system(build_level_graph.after(crate::door::validate_door_links));
+    }
+}
+
+fn build_level_graph(
+    mut asset_events: EventReader<AssetEvent<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    mut level_graph: ResMut<LevelGraph>,
+) {
+    for event in asset_events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = event {
+            if let Some(ldtk) = ldtk_assets.get(handle) {
+                *level_graph = LevelGraph::from_project(&ldtk.project);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 0 leads to 1, which branches to 2 and 3
+    fn branching_graph() -> LevelGraph {
+        LevelGraph {
+            order: vec![0, 1, 2, 3],
+            links: vec![vec![1], vec![2, 3], vec![], vec![]],
+        }
+    }
+
+    #[test]
+    fn next_level_starts_at_the_first_level() {
+        assert_eq!(branching_graph().next_level(|_| false), Some(0));
+    }
+
+    #[test]
+    fn next_level_follows_completed_levels() {
+        let graph = branching_graph();
+
+        assert_eq!(graph.next_level(|level| level == 0), Some(1));
+        assert_eq!(graph.next_level(|level| level <= 1), Some(2));
+        assert_eq!(graph.next_level(|level| level != 3), Some(3));
+    }
+
+    #[test]
+    fn next_level_is_none_when_everything_is_done() {
+        assert_eq!(branching_graph().next_level(|_| true), None);
+        assert_eq!(LevelGraph::default().next_level(|_| false), None);
+    }
+}
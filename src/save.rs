@@ -0,0 +1,732 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy_pkv::{GetError, PkvStore};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::checkpoint::PlayerDiedEvent;
+use crate::magnet::SoulMagnet;
+use crate::settings::Settings;
+use crate::soul::CollectedSoulEvent;
+use crate::upgrade::Upgrade;
+use crate::world::{ChangeLevelEvent, ReloadWorldEvent};
+use crate::GameState;
+
+pub struct SavePlugin;
+
+/// Everything the game remembers between runs, stored in the `PkvStore` as one value per slot
+#[derive(Resource, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct SaveFile {
+    /// Schema the save was written with, see `SaveFile::migrate`
+    pub version: u32,
+    pub completed_levels: BTreeSet<usize>,
+    /// Fastest completion of each level in seconds, by level index
+    pub best_times: BTreeMap<usize, f32>,
+    pub souls_collected: usize,
+    pub deaths: usize,
+    /// Seconds spent playing, banked whenever the game is paused or left
+    pub play_time: f32,
+    pub unlocked_abilities: BTreeSet<String>,
+    pub grain: usize,
+    pub upgrades: BTreeMap<String, usize>,
+    /// IIDs of the secret areas the player has found
+    pub found_secrets: BTreeSet<String>,
+    /// `Settings::VERSION` the last time the game was saved
+    pub settings_version: u32,
+    /// Set when the stored save couldn't be read or is from a newer game, so it's never
+    /// written over with what this build made of it
+    #[serde(skip)]
+    pub read_only: bool,
+}
+
+impl Default for SaveFile {
+    fn default() -> Self {
+        SaveFile {
+            version: SaveFile::VERSION,
+            completed_levels: BTreeSet::new(),
+            best_times: BTreeMap::new(),
+            souls_collected: 0,
+            deaths: 0,
+            play_time: 0.,
+            unlocked_abilities: BTreeSet::new(),
+            grain: 0,
+            upgrades: BTreeMap::new(),
+            found_secrets: BTreeSet::new(),
+            settings_version: Settings::VERSION,
+            read_only: false,
+        }
+    }
+}
+
+impl SaveFile {
+    /// Bump this when a change needs more than `serde(default)` to load older saves
+    pub const VERSION: u32 = 1;
+
+    /// Where saves were kept before there were slots, migrated into the first slot
+    const KEY: &'static str = "SaveFile";
+
+    /// Number of save slots the player can pick between
+    pub const SLOTS: usize = 3;
+
+    /// Where saves are exported to and imported from by the menus, next to `settings.ron`
+    pub const EXPORT_PATH: &'static str = "save.ron";
+
+    /// Command-line flag naming a save file to import into the current slot on startup
+    const IMPORT_FLAG: &'static str = "--import-save";
+
+    const ABILITIES: [&'static str; 1] = [SoulMagnet::ABILITY_NAME];
+
+    /// Saves from before `SaveFile` existed only ever had levels below this marked complete
+    const LEGACY_LEVEL_LIMIT: usize = 256;
+
+    fn slot_key(slot: usize) -> String {
+        format!("Slot{}{}", slot, SaveFile::KEY)
+    }
+
+    /// Reads the slot's save from the store, bringing older saves up to date
+    pub fn load(data_store: &PkvStore, slot: usize) -> Self {
+        match data_store.get::<SaveFile>(SaveFile::slot_key(slot).as_str()) {
+            Ok(save_file) => save_file.migrate(),
+            Err(GetError::NotFound) if slot == 0 => SaveFile::load_unslotted(data_store),
+            Err(GetError::NotFound) => SaveFile::default(),
+            Err(error) => SaveFile::unreadable(slot, error),
+        }
+    }
+
+    /// Reads the selected slot's save, `SaveFile` has a `Default` so it can't be `FromWorld`
+    fn load_selected(world: &mut World) -> Self {
+        let slot = world.resource::<SaveSlot>().index;
+        let save_file = SaveFile::load(world.resource::<PkvStore>(), slot);
+
+        // Keeps migrated saves from being rebuilt from the old keys every launch, unreadable
+        // and newer saves are read only and left as they are
+        save_file.store(&mut world.resource_mut::<PkvStore>(), slot);
+
+        save_file
+    }
+
+    fn load_unslotted(data_store: &PkvStore) -> Self {
+        match data_store.get::<SaveFile>(SaveFile::KEY) {
+            Ok(save_file) => save_file.migrate(),
+            Err(GetError::NotFound) => SaveFile::from_legacy_keys(data_store),
+            Err(error) => SaveFile::unreadable(0, error),
+        }
+    }
+
+    /// Stands in for a save that's there but can't be read, leaving the stored one alone
+    fn unreadable(slot: usize, error: GetError) -> Self {
+        println!(
+            "couldn't read save slot {}, progress won't be saved until it's deleted: {}",
+            slot, error
+        );
+
+        SaveFile {
+            read_only: true,
+            ..Default::default()
+        }
+    }
+
+    /// Writes the save to the slot, returning false if it's read only and wasn't written
+    pub fn store(&self, data_store: &mut PkvStore, slot: usize) -> bool {
+        if self.read_only {
+            println!("not writing a read only save to slot {}", slot);
+            return false;
+        }
+
+        data_store
+            .set(SaveFile::slot_key(slot).as_str(), self)
+            .unwrap();
+        true
+    }
+
+    /// Empties the slot, leaving a fresh save behind so the first slot isn't migrated again
+    pub fn delete(data_store: &mut PkvStore, slot: usize) {
+        println!("deleted save slot {}", slot);
+        SaveFile::default().store(data_store, slot);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == SaveFile::default()
+    }
+
+    /// Share of the levels completed, out of 100
+    pub fn completion_percent(&self, level_count: usize) -> usize {
+        if level_count == 0 {
+            return 0;
+        }
+
+        self.completed_levels.len().min(level_count) * 100 / level_count
+    }
+
+    /// Play time as hours, minutes and seconds
+    pub fn play_time_label(&self) -> String {
+        let seconds = self.play_time as u64;
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    }
+
+    fn migrate(mut self) -> Self {
+        // Writing it back would lose whatever the newer game added, so play it without saving
+        if self.version > SaveFile::VERSION {
+            println!(
+                "save file version {} is newer than this game's {}, it won't be saved over",
+                self.version,
+                SaveFile::VERSION
+            );
+            self.read_only = true;
+            return self;
+        }
+
+        // Fields added since are filled in by `serde(default)`, anything needing more goes here
+        self.version = SaveFile::VERSION;
+        self.settings_version = Settings::VERSION;
+        self
+    }
+
+    /// Builds a save from the separate keys older versions of the game stored progress in
+    fn from_legacy_keys(data_store: &PkvStore) -> Self {
+        SaveFile::from_legacy(
+            |key| data_store.get::<bool>(key).unwrap_or(false),
+            |key| data_store.get::<usize>(key).ok(),
+        )
+    }
+
+    fn from_legacy(is_set: impl Fn(&str) -> bool, number: impl Fn(&str) -> Option<usize>) -> Self {
+        let mut save_file = SaveFile {
+            completed_levels: (0..SaveFile::LEGACY_LEVEL_LIMIT)
+                .filter(|level| is_set(format!("Level{}Completed", level).as_str()))
+                .collect(),
+            grain: number("Grain").unwrap_or(0),
+            ..Default::default()
+        };
+
+        for ability in SaveFile::ABILITIES {
+            if is_set(format!("{}Unlocked", ability).as_str()) {
+                save_file.unlocked_abilities.insert(String::from(ability));
+            }
+        }
+
+        for upgrade in Upgrade::ALL {
+            if let Some(level) = number(format!("{}Upgrade", upgrade.name()).as_str()) {
+                save_file
+                    .upgrades
+                    .insert(String::from(upgrade.name()), level);
+            }
+        }
+
+        // Secrets were saved by IID, which can't be listed, so they have to be found again
+
+        if save_file != SaveFile::default() {
+            println!(
+                "migrated save from legacy keys, {} levels completed",
+                save_file.completed_levels.len()
+            );
+        }
+
+        save_file
+    }
+
+    /// Writes the save out as RON, for moving it to another machine
+    pub fn export(&self, path: &str) -> Result<(), String> {
+        std::fs::write(path, self.to_ron()?)
+            .map_err(|error| format!("Couldn't write {}: {}", path, error))?;
+
+        println!("exported save to {}", path);
+        Ok(())
+    }
+
+    /// Reads an exported save, refusing it if it's corrupted or from a newer game
+    pub fn import(path: &str) -> Result<SaveFile, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| format!("Couldn't read {}: {}", path, error))?;
+
+        let save_file =
+            SaveFile::from_ron(&contents).map_err(|error| format!("{} {}", path, error))?;
+
+        println!("imported save from {}", path);
+        Ok(save_file)
+    }
+
+    fn to_ron(&self) -> Result<String, String> {
+        let pretty = ron::ser::PrettyConfig::new().depth_limit(2);
+        ron::ser::to_string_pretty(self, pretty)
+            .map_err(|error| format!("Couldn't write save: {}", error))
+    }
+
+    fn from_ron(contents: &str) -> Result<SaveFile, String> {
+        let save_file: SaveFile =
+            ron::from_str(contents).map_err(|error| format!("isn't a save file: {}", error))?;
+
+        let errors = save_file.schema_errors();
+        if !errors.is_empty() {
+            return Err(format!("is corrupted: {}", errors.join(", ")));
+        }
+
+        Ok(save_file.migrate())
+    }
+
+    /// Values serde accepts that no version of the game would have saved
+    fn schema_errors(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.version > SaveFile::VERSION {
+            errors.push(format!(
+                "save version {} is newer than this game's {}",
+                self.version,
+                SaveFile::VERSION
+            ));
+        }
+
+        if !self.play_time.is_finite() || self.play_time < 0. {
+            errors.push(format!("play time {} isn't a duration", self.play_time));
+        }
+
+        for (level, time) in self.best_times.iter() {
+            if !self.completed_levels.contains(level) {
+                errors.push(format!(
+                    "level {} has a best time but isn't completed",
+                    level
+                ));
+            }
+            if !time.is_finite() || *time < 0. {
+                errors.push(format!(
+                    "best time {} on level {} isn't a duration",
+                    time, level
+                ));
+            }
+        }
+
+        for ability in self.unlocked_abilities.iter() {
+            if !SaveFile::ABILITIES.contains(&ability.as_str()) {
+                errors.push(format!("unknown ability \"{}\"", ability));
+            }
+        }
+
+        for (name, level) in self.upgrades.iter() {
+            let upgrade = Upgrade::ALL
+                .into_iter()
+                .find(|upgrade| upgrade.name() == name);
+
+            match upgrade {
+                Some(upgrade) if *level > upgrade.max_level() => errors.push(format!(
+                    "upgrade \"{}\" is level {}, past its max of {}",
+                    name,
+                    level,
+                    upgrade.max_level()
+                )),
+                Some(_) => (),
+                None => errors.push(format!("unknown upgrade \"{}\"", name)),
+            }
+        }
+
+        errors
+    }
+
+    pub fn has_completed_level(&self, level: usize) -> bool {
+        self.completed_levels.contains(&level)
+    }
+
+    /// Marks the level complete, keeping the time if it's the best so far
+    pub fn complete_level(&mut self, level: usize, time: f32) {
+        println!("completed level {} in {:.2}s", level, time);
+        self.completed_levels.insert(level);
+
+        let best = self.best_times.entry(level).or_insert(time);
+        *best = best.min(time);
+    }
+
+    pub fn has_unlocked_ability(&self, ability: &str) -> bool {
+        self.unlocked_abilities.contains(ability)
+    }
+
+    pub fn unlock_ability(&mut self, ability: &str) {
+        println!("unlocked ability {}", ability);
+        self.unlocked_abilities.insert(String::from(ability));
+    }
+
+    pub fn has_found_secret(&self, iid: &str) -> bool {
+        self.found_secrets.contains(iid)
+    }
+
+    pub fn mark_secret_found(&mut self, iid: &str) {
+        println!("found secret {}", iid);
+        self.found_secrets.insert(String::from(iid));
+    }
+
+    pub fn upgrade_level(&self, upgrade: &str) -> usize {
+        self.upgrades.get(upgrade).copied().unwrap_or(0)
+    }
+
+    pub fn set_upgrade_level(&mut self, upgrade: &str, level: usize) {
+        println!("upgraded {} to level {}", upgrade, level);
+        self.upgrades.insert(String::from(upgrade), level);
+    }
+}
+
+/// Which slot `SaveFile` is loaded from and written back to, remembered between runs
+#[derive(Resource, Clone, Copy, Default, Debug, PartialEq)]
+pub struct SaveSlot {
+    pub index: usize,
+}
+
+impl SaveSlot {
+    const KEY: &'static str = "SaveSlot";
+
+    fn load(data_store: &PkvStore) -> Self {
+        let index = data_store.get::<usize>(SaveSlot::KEY).unwrap_or(0);
+
+        SaveSlot {
+            index: index.min(SaveFile::SLOTS - 1),
+        }
+    }
+
+    fn store(&self, data_store: &mut PkvStore) {
+        data_store.set(SaveSlot::KEY, &self.index).unwrap();
+    }
+}
+
+/// Time spent in the current level, for best times
+#[derive(Resource, Default, Debug)]
+pub struct LevelClock {
+    pub elapsed: f32,
+    /// Play time not yet added to the save, kept out of it so it isn't written every frame
+    pub unbanked: f32,
+}
+
+/// Result of the last save import or export, shown in the menus
+#[derive(Resource, Default, Debug)]
+pub struct SaveTransferStatus {
+    pub message: String,
+}
+
+impl LevelClock {
+    /// Longest stretch of play time that can be lost if the game doesn't exit cleanly
+    pub const BANK_INTERVAL: f32 = 30.;
+}
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        let save_slot = SaveSlot::load(app.world.resource::<PkvStore>());
+        app.insert_resource(save_slot);
+        let save_file = SaveFile::load_selected(&mut app.world);
+
+        app.insert_resource(save_file)
+            .init_resource::<LevelClock>()
+            .init_resource::<SaveTransferStatus>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(tick_level_clock.after(crate::world::switch_level))
+                    .with_system(count_deaths)
+                    .with_system(count_souls),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(reset_level_clock))
+            .add_startup_system(import_save_from_args)
+            .add_system_set(SystemSet::on_pause(GameState::Playing).with_system(bank_play_time))
+            .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(bank_play_time))
+            .add_system_to_stage(CoreStage::Last, bank_on_exit.before(write_save_file))
+            .add_system_to_stage(CoreStage::Last, write_save_file);
+    }
+}
+
+fn write_save_file(
+    save_slot: Res<SaveSlot>,
+    save_file: Res<SaveFile>,
+    mut data_store: ResMut<PkvStore>,
+) {
+    if save_slot.is_changed() && !save_slot.is_added() {
+        save_slot.store(&mut data_store);
+    }
+
+    if save_file.is_changed() && !save_file.is_added() && !save_file.read_only {
+        save_file.store(&mut data_store, save_slot.index);
+    }
+}
+
+/// Imports the save named after `SaveFile::IMPORT_FLAG`, if the game was started with one
+fn import_save_from_args(
+    mut save_file: ResMut<SaveFile>,
+    save_slot: Res<SaveSlot>,
+    mut data_store: ResMut<PkvStore>,
+    mut status: ResMut<SaveTransferStatus>,
+) {
+    let mut args = std::env::args().skip_while(|arg| arg != SaveFile::IMPORT_FLAG);
+
+    if args.next().is_none() {
+        return;
+    }
+
+    let Some(path) = args.next() else {
+        status.message = format!("{} needs the path of a save file", SaveFile::IMPORT_FLAG);
+        println!("{}", status.message);
+        return;
+    };
+
+    match SaveFile::import(&path) {
+        Ok(imported) => {
+            println!("replaced save slot {} with {}", save_slot.index, path);
+            // Stored here since `write_save_file` skips the save's first frame
+            imported.store(&mut data_store, save_slot.index);
+            *save_file = imported;
+            status.message = format!("Loaded {}", path);
+        }
+        Err(error) => {
+            println!("{}", error);
+            status.message = error;
+        }
+    }
+}
+
+fn bank_play_time(mut level_clock: ResMut<LevelClock>, mut save_file: ResMut<SaveFile>) {
+    save_file.play_time += level_clock.unbanked;
+    level_clock.unbanked = 0.;
+}
+
+fn bank_on_exit(
+    mut exit_events: EventReader<AppExit>,
+    level_clock: ResMut<LevelClock>,
+    save_file: ResMut<SaveFile>,
+) {
+    if exit_events.iter().count() > 0 {
+        bank_play_time(level_clock, save_file);
+    }
+}
+
+fn reset_level_clock(mut level_clock: ResMut<LevelClock>) {
+    level_clock.elapsed = 0.;
+}
+
+/// Walking between streamed rooms keeps the clock going, only doors and restarts reset it
+fn tick_level_clock(
+    time: Res<Time>,
+    mut change_events: EventReader<ChangeLevelEvent>,
+    mut reload_events: EventReader<ReloadWorldEvent>,
+    mut level_clock: ResMut<LevelClock>,
+    save_file: ResMut<SaveFile>,
+) {
+    let restarted = change_events.iter().count() > 0 || reload_events.iter().count() > 0;
+
+    if restarted {
+        level_clock.elapsed = 0.;
+    } else {
+        level_clock.elapsed += time.delta_seconds();
+    }
+
+    level_clock.unbanked += time.delta_seconds();
+
+    if level_clock.unbanked >= LevelClock::BANK_INTERVAL {
+        bank_play_time(level_clock, save_file);
+    }
+}
+
+fn count_deaths(mut died_events: EventReader<PlayerDiedEvent>, mut save_file: ResMut<SaveFile>) {
+    for _ in died_events.iter() {
+        save_file.deaths += 1;
+    }
+}
+
+fn count_souls(mut soul_events: EventReader<CollectedSoulEvent>, mut save_file: ResMut<SaveFile>) {
+    for soul_ev in soul_events.iter() {
+        save_file.souls_collected += soul_ev.value;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn legacy(keys: &[(&str, usize)]) -> SaveFile {
+        let keys: HashMap<&str, usize> = keys.iter().copied().collect();
+        SaveFile::from_legacy(
+            |key| keys.get(key).map_or(false, |value| *value != 0),
+            |key| keys.get(key).copied(),
+        )
+    }
+
+    #[test]
+    fn legacy_keys_become_a_save() {
+        let save_file = legacy(&[
+            ("Level0Completed", 1),
+            ("Level3Completed", 1),
+            ("Level4Completed", 0),
+            ("SoulMagnetUnlocked", 1),
+            ("Grain", 12),
+            ("AirJumpUpgrade", 2),
+        ]);
+
+        assert_eq!(save_file.completed_levels, BTreeSet::from([0, 3]));
+        assert!(save_file
+            .unlocked_abilities
+            .contains(SoulMagnet::ABILITY_NAME));
+        assert_eq!(save_file.grain, 12);
+        assert_eq!(save_file.upgrades.get("AirJump"), Some(&2));
+        assert_eq!(save_file.upgrades.get("MoveSpeed"), None);
+        assert_eq!(save_file.version, SaveFile::VERSION);
+    }
+
+    #[test]
+    fn no_legacy_keys_is_an_empty_save() {
+        assert!(legacy(&[]).is_empty());
+    }
+
+    #[test]
+    fn completion_percent() {
+        let mut save_file = SaveFile::default();
+        assert_eq!(save_file.completion_percent(0), 0);
+        assert_eq!(save_file.completion_percent(3), 0);
+
+        save_file.completed_levels.extend([0, 2]);
+        assert_eq!(save_file.completion_percent(3), 66);
+        assert_eq!(save_file.completion_percent(1), 100);
+    }
+
+    #[test]
+    fn play_time_label() {
+        let mut save_file = SaveFile::default();
+        assert_eq!(save_file.play_time_label(), "0:00:00");
+
+        save_file.play_time = 59.9;
+        assert_eq!(save_file.play_time_label(), "0:00:59");
+
+        save_file.play_time = 3600. * 12. + 61.;
+        assert_eq!(save_file.play_time_label(), "12:01:01");
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut save_file = SaveFile::default();
+        save_file.complete_level(2, 31.5);
+        save_file.grain = 7;
+        save_file.found_secrets.insert(String::from("secret-iid"));
+
+        let json = serde_json::to_string(&save_file).unwrap();
+        assert_eq!(serde_json::from_str::<SaveFile>(&json).unwrap(), save_file);
+    }
+
+    fn valid_save() -> SaveFile {
+        let mut save_file = SaveFile::default();
+        save_file.complete_level(0, 12.5);
+        save_file.play_time = 80.;
+        save_file
+            .unlocked_abilities
+            .insert(String::from(SoulMagnet::ABILITY_NAME));
+        save_file.upgrades.insert(
+            String::from(Upgrade::AirJump.name()),
+            Upgrade::AirJump.max_level(),
+        );
+        save_file
+    }
+
+    fn import_errors(save_file: &SaveFile) -> String {
+        SaveFile::from_ron(&save_file.to_ron().unwrap()).unwrap_err()
+    }
+
+    #[test]
+    fn exported_saves_import() {
+        let save_file = valid_save();
+
+        assert!(save_file.schema_errors().is_empty());
+        assert_eq!(
+            SaveFile::from_ron(&save_file.to_ron().unwrap()).unwrap(),
+            save_file
+        );
+    }
+
+    #[test]
+    fn garbage_is_refused() {
+        assert!(SaveFile::from_ron("not a save").is_err());
+        assert!(SaveFile::from_ron("(grain: -3)").is_err());
+    }
+
+    #[test]
+    fn newer_saves_are_refused() {
+        let mut save_file = valid_save();
+        save_file.version = SaveFile::VERSION + 1;
+
+        assert!(import_errors(&save_file).contains("newer"));
+    }
+
+    #[test]
+    fn bad_times_are_refused() {
+        let mut save_file = valid_save();
+        save_file.play_time = -1.;
+        assert!(import_errors(&save_file).contains("play time"));
+
+        let mut save_file = valid_save();
+        save_file.play_time = f32::NAN;
+        assert_eq!(save_file.schema_errors().len(), 1);
+
+        let mut save_file = valid_save();
+        save_file.best_times.insert(0, f32::INFINITY);
+        assert_eq!(save_file.schema_errors().len(), 1);
+    }
+
+    #[test]
+    fn best_times_need_completed_levels() {
+        let mut save_file = valid_save();
+        save_file.best_times.insert(4, 20.);
+
+        assert!(import_errors(&save_file).contains("level 4"));
+    }
+
+    #[test]
+    fn unknown_abilities_are_refused() {
+        let mut save_file = valid_save();
+        save_file
+            .unlocked_abilities
+            .insert(String::from("Teleport"));
+
+        assert!(import_errors(&save_file).contains("Teleport"));
+    }
+
+    #[test]
+    fn unknown_upgrades_are_refused() {
+        let mut save_file = valid_save();
+        save_file.upgrades.insert(String::from("Teleport"), 1);
+
+        assert!(import_errors(&save_file).contains("Teleport"));
+    }
+
+    #[test]
+    fn upgrades_past_max_are_refused() {
+        let mut save_file = valid_save();
+        save_file.upgrades.insert(
+            String::from(Upgrade::MoveSpeed.name()),
+            Upgrade::MoveSpeed.max_level() + 1,
+        );
+
+        assert!(import_errors(&save_file).contains("past its max"));
+    }
+
+    #[test]
+    fn missing_fields_are_defaulted() {
+        let save_file: SaveFile =
+            serde_json::from_str(r#"{"version":0,"completed_levels":[1]}"#).unwrap();
+        let save_file = save_file.migrate();
+
+        assert_eq!(save_file.version, SaveFile::VERSION);
+        assert_eq!(save_file.completed_levels, BTreeSet::from([1]));
+        assert_eq!(save_file.grain, 0);
+        assert!(save_file.found_secrets.is_empty());
+        assert!(!save_file.read_only);
+    }
+
+    #[test]
+    fn newer_versions_are_left_alone() {
+        let save_file: SaveFile = serde_json::from_str(
+            r#"{"version":99,"settings_version":99,"grain":3,"future_field":true}"#,
+        )
+        .unwrap();
+        let save_file = save_file.migrate();
+
+        assert!(save_file.read_only);
+        assert_eq!(save_file.version, 99);
+        assert_eq!(save_file.settings_version, 99);
+        assert_eq!(save_file.grain, 3);
+    }
+}
@@ -0,0 +1,232 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::Level;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+use crate::loading::AudioAssets;
+use crate::pickup::CollectedSecretEvent;
+use crate::player::Player;
+use crate::save::SaveFile;
+use crate::world::Labeled;
+use crate::GameState;
+
+pub struct SecretPlugin;
+
+/// Tile layer drawn over everything else, hiding the secret rooms underneath
+pub const FOREGROUND_LAYER: &str = "Foreground";
+
+/// Keeps the foreground above the player whatever order the layers spawn in
+const FOREGROUND_Z: f32 = 10.;
+
+/// An area hidden by the foreground layer, which fades away while the player is inside it
+#[derive(Component, Clone, Default, Debug)]
+pub struct SecretArea {
+    pub iid: String,
+    pub half_extents: Vec2,
+    /// How far the foreground over the area has faded, from 0 to 1
+    pub reveal: f32,
+}
+
+impl SecretArea {
+    const FADE_SPEED: f32 = 3.;
+
+    fn contains(&self, center: Vec2, point: Vec2) -> bool {
+        let offset = point - center;
+        offset.x.abs() <= self.half_extents.x && offset.y.abs() <= self.half_extents.y
+    }
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct SecretAreaBundle {
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+    pub label: Labeled,
+    pub secret_area: SecretArea,
+}
+
+/// Secret areas in every level of the project, for counting how many have been found
+#[derive(Resource, Default, Debug, Clone)]
+pub struct SecretIndex {
+    /// IIDs of each level's secret areas, indexed like the project's levels
+    pub levels: Vec<Vec<String>>,
+}
+
+impl SecretIndex {
+    /// Percentage of the level's secrets found, if it has any
+    pub fn found_percent(&self, level: usize, has_found: impl Fn(&str) -> bool) -> Option<usize> {
+        let secrets = self
+            .levels
+            .get(level)
+            .filter(|secrets| !secrets.is_empty())?;
+        let found = secrets.iter().filter(|iid| has_found(iid)).count();
+
+        Some(found * 100 / secrets.len())
+    }
+}
+
+impl Plugin for SecretPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SecretIndex>()
+            .add_system(build_secret_index)
+            .add_system(raise_foreground)
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(discover_secrets)
+                    .with_system(collect_secret_pickups)
+                    .with_system(fade_foreground.after(discover_secrets)),
+            );
+    }
+}
+
+impl LdtkEntity for SecretAreaBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        _layer_instance: &LayerInstance,
+        _tileset: Option<&Handle<Image>>,
+        _tileset_definition: Option<&TilesetDefinition>,
+        _asset_server: &AssetServer,
+        _texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Self {
+        SecretAreaBundle {
+            label: Labeled {
+                name: String::from("secret area"),
+            },
+            secret_area: SecretArea {
+                iid: entity_instance.iid.clone(),
+                half_extents: Vec2::new(
+                    entity_instance.width as f32,
+                    entity_instance.height as f32,
+                ) / 2.,
+                reveal: 0.,
+            },
+            ..Default::default()
+        }
+    }
+}
+
+fn level_secrets(level: &Level) -> Vec<String> {
+    level
+        .layer_instances
+        .iter()
+        .flatten()
+        .flat_map(|layer| layer.entity_instances.iter())
+        // Secret pickups count the same as the areas hidden under the foreground
+        .filter(|entity_instance| {
+            entity_instance.identifier == "SecretArea" || entity_instance.identifier == "Secret"
+        })
+        .map(|entity_instance| entity_instance.iid.clone())
+        .collect()
+}
+
+fn build_secret_index(
+    mut asset_events: EventReader<AssetEvent<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    mut secret_index: ResMut<SecretIndex>,
+) {
+    for event in asset_events.iter() {
+        if let AssetEvent::Created { handle } | AssetEvent::Modified { handle } = event {
+            if let Some(ldtk) = ldtk_assets.get(handle) {
+                secret_index.levels = ldtk.project.levels.iter().map(level_secrets).collect();
+            }
+        }
+    }
+}
+
+fn raise_foreground(
+    mut layer_query: Query<(&LayerMetadata, &mut Transform), Added<LayerMetadata>>,
+) {
+    for (layer, mut transform) in &mut layer_query {
+        if layer.identifier == FOREGROUND_LAYER {
+            transform.translation.z += FOREGROUND_Z;
+        }
+    }
+}
+
+/// Fades the areas the player is in, and saves the first time each one is found
+fn discover_secrets(
+    time: Res<Time>,
+    player_query: Query<&GlobalTransform, With<Player>>,
+    mut area_query: Query<(&GlobalTransform, &mut SecretArea)>,
+    mut save_file: ResMut<SaveFile>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let player_pos = player_transform.translation().truncate();
+
+    for (transform, mut area) in &mut area_query {
+        let inside = area.contains(transform.translation().truncate(), player_pos);
+
+        if inside && !save_file.has_found_secret(&area.iid) {
+            save_file.mark_secret_found(&area.iid);
+            audio.play(audio_assets.secret_pickup.clone());
+        }
+
+        let target = if inside { 1. } else { 0. };
+        let step = SecretArea::FADE_SPEED * time.delta_seconds();
+        let reveal = area.reveal + (target - area.reveal).clamp(-step, step);
+
+        if reveal != area.reveal {
+            area.reveal = reveal;
+        }
+    }
+}
+
+fn collect_secret_pickups(
+    mut secret_events: EventReader<CollectedSecretEvent>,
+    mut save_file: ResMut<SaveFile>,
+) {
+    for secret_ev in secret_events.iter() {
+        if !save_file.has_found_secret(&secret_ev.iid) {
+            save_file.mark_secret_found(&secret_ev.iid);
+        }
+    }
+}
+
+fn fade_foreground(
+    area_query: Query<(&GlobalTransform, &SecretArea)>,
+    changed_query: Query<(), Changed<SecretArea>>,
+    tilemap_query: Query<(
+        &LayerMetadata,
+        &GlobalTransform,
+        &TilemapGridSize,
+        &TilemapType,
+        &TileStorage,
+    )>,
+    mut tile_query: Query<(&TilePos, &mut TileColor)>,
+) {
+    if changed_query.is_empty() {
+        return;
+    }
+
+    for (layer, map_transform, grid_size, map_type, storage) in &tilemap_query {
+        if layer.identifier != FOREGROUND_LAYER {
+            continue;
+        }
+
+        for tile_entity in storage.iter().flatten() {
+            let Ok((tile_pos, mut color)) = tile_query.get_mut(*tile_entity) else {
+                continue;
+            };
+
+            let tile_center = map_transform.translation().truncate()
+                + tile_pos.center_in_world(grid_size, map_type);
+
+            let reveal = area_query
+                .iter()
+                .filter(|(transform, area)| {
+                    area.contains(transform.translation().truncate(), tile_center)
+                })
+                .map(|(_, area)| area.reveal)
+                .fold(0., f32::max);
+
+            let alpha = 1. - reveal;
+            if color.0.a() != alpha {
+                color.0.set_a(alpha);
+            }
+        }
+    }
+}
@@ -17,6 +17,9 @@ impl Plugin for SettingsPlugin {
 }
 
 impl Settings {
+    /// Bump this when the fields change, saves record which version they were made with
+    pub const VERSION: u32 = 1;
+
     fn settings_file_path() -> String {
         String::from("settings.ron")
     }
@@ -0,0 +1,607 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_rapier2d::prelude::*;
+
+use crate::actor::{Actor, Scythable};
+use crate::player::Player;
+use crate::soul::SoulWallet;
+use crate::world::Labeled;
+use crate::GameState;
+
+pub struct SignalPlugin;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SignalAction {
+    On,
+    Off,
+    #[default]
+    Toggle,
+}
+
+impl SignalAction {
+    pub fn from_name(name: &str) -> Option<SignalAction> {
+        match name.to_lowercase().as_str() {
+            "on" => Some(SignalAction::On),
+            "off" => Some(SignalAction::Off),
+            "toggle" => Some(SignalAction::Toggle),
+            _ => None,
+        }
+    }
+
+    pub fn opposite(&self) -> SignalAction {
+        match self {
+            SignalAction::On => SignalAction::Off,
+            SignalAction::Off => SignalAction::On,
+            SignalAction::Toggle => SignalAction::Toggle,
+        }
+    }
+}
+
+/// Sent by a source to every entity it is linked to in LDtk
+pub struct SignalEvent {
+    pub targets: Vec<String>,
+    pub action: SignalAction,
+}
+
+/// On/off state of anything that can be driven by signals, looked up by its LDtk IID
+#[derive(Component, Clone, Debug, Default)]
+pub struct SignalReceiver {
+    pub iid: String,
+    pub active: bool,
+    pub default_active: bool,
+    /// Seconds before returning to the default state after being switched
+    pub reset_time: Option<f32>,
+    pub reset_timer: f32,
+}
+
+impl SignalReceiver {
+    pub fn new(iid: String, default_active: bool) -> Self {
+        SignalReceiver {
+            iid,
+            active: default_active,
+            default_active,
+            reset_time: None,
+            reset_timer: 0.,
+        }
+    }
+
+    /// Reads the shared receiver fields, any LDtk entity can be a signal target
+    pub fn from_entity_instance(entity_instance: &EntityInstance, default_active: bool) -> Self {
+        let mut receiver = SignalReceiver::new(entity_instance.iid.clone(), default_active);
+
+        for field in entity_instance.field_instances.iter() {
+            match field.identifier.as_str() {
+                "StartActive" => {
+                    if let FieldValue::Bool(value) = field.value {
+                        receiver.default_active = value;
+                        receiver.active = value;
+                    }
+                }
+                "ResetTime" => {
+                    if let FieldValue::Float(value) = field.value {
+                        receiver.reset_time = value.filter(|time| *time > 0.);
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        receiver
+    }
+
+    pub fn apply(&mut self, action: SignalAction) {
+        self.active = match action {
+            SignalAction::On => true,
+            SignalAction::Off => false,
+            SignalAction::Toggle => !self.active,
+        };
+        self.reset_timer = 0.;
+    }
+
+    /// Advances the reset timer, returning true if the receiver went back to its default
+    pub fn tick(&mut self, delta_seconds: f32) -> bool {
+        if let Some(reset_time) = self.reset_time {
+            if self.active != self.default_active {
+                self.reset_timer += delta_seconds;
+
+                if self.reset_timer >= reset_time {
+                    self.active = self.default_active;
+                    self.reset_timer = 0.;
+                    return true;
+                }
+            }
+        }
+
+        false
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum SignalTrigger {
+    /// Hit with the scythe
+    #[default]
+    Lever,
+    /// Something standing on it, sends the opposite action when released
+    PressurePlate,
+    /// The player carrying enough souls
+    SoulCount { souls: usize, group: Option<String> },
+}
+
+/// Sends a signal to its linked targets when triggered
+#[derive(Component, Clone, Debug, Default)]
+pub struct SignalSource {
+    pub targets: Vec<String>,
+    pub action: SignalAction,
+    pub trigger: SignalTrigger,
+    pub triggered: bool,
+    pub cooldown: f32,
+}
+
+impl SignalSource {
+    /// Reads the linked targets and action, leaving the trigger to the caller
+    pub fn from_entity_instance(entity_instance: &EntityInstance, trigger: SignalTrigger) -> Self {
+        let mut source = SignalSource {
+            trigger,
+            ..Default::default()
+        };
+
+        for field in entity_instance.field_instances.iter() {
+            match (field.identifier.as_str(), &field.value) {
+                ("Targets", FieldValue::EntityRefs(references)) => {
+                    source.targets.extend(
+                        references
+                            .iter()
+                            .flatten()
+                            .map(|reference| reference.entity_iid.clone()),
+                    );
+                }
+                ("Target", FieldValue::EntityRef(Some(reference))) => {
+                    source.targets.push(reference.entity_iid.clone());
+                }
+                ("Action", FieldValue::String(Some(value))) => {
+                    if let Some(action) = SignalAction::from_name(value) {
+                        source.action = action;
+                    } else {
+                        println!("Unknown signal action \"{}\" on LDtk object!", value);
+                    }
+                }
+                ("Souls", FieldValue::Int(Some(value))) => {
+                    if let SignalTrigger::SoulCount { souls, .. } = &mut source.trigger {
+                        *souls = (*value).max(0) as usize;
+                    }
+                }
+                ("SoulGroup", FieldValue::String(value)) => {
+                    if let SignalTrigger::SoulCount { group, .. } = &mut source.trigger {
+                        *group = value.clone().filter(|group| !group.is_empty());
+                    }
+                }
+                (unknown, _) => println!(
+                    "Unknown field \"{}\" on LDtk {} object!",
+                    unknown,
+                    entity_instance.identifier.to_lowercase()
+                ),
+            }
+        }
+
+        source
+    }
+
+    /// Action to send if the source is switching on or off, the opposite one when switching off
+    pub fn edge_action(&self, triggered: bool) -> Option<SignalAction> {
+        if triggered == self.triggered {
+            None
+        } else if triggered {
+            Some(self.action)
+        } else {
+            Some(self.action.opposite())
+        }
+    }
+
+    /// Switches a lever the other way, returning the action to send for the way it moved
+    pub fn flip(&mut self) -> SignalAction {
+        self.triggered = !self.triggered;
+
+        if self.triggered {
+            self.action
+        } else {
+            self.action.opposite()
+        }
+    }
+
+    /// Whether the wallet holds enough souls, or `None` if this isn't a soul count source
+    pub fn soul_count_reached(&self, wallet: &SoulWallet) -> Option<bool> {
+        match &self.trigger {
+            SignalTrigger::SoulCount { souls, group } => {
+                Some(wallet.count(group.as_deref()) >= *souls)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn signal(&self, action: SignalAction) -> SignalEvent {
+        SignalEvent {
+            targets: self.targets.clone(),
+            action,
+        }
+    }
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct SignalSourceBundle {
+    #[bundle]
+    pub sprite_bundle: SpriteBundle,
+    pub collider: Collider,
+    pub sensor: Sensor,
+    pub label: Labeled,
+    pub source: SignalSource,
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct LeverBundle {
+    #[bundle]
+    pub source_bundle: SignalSourceBundle,
+    pub scythable: Scythable,
+}
+
+impl Plugin for SignalPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SignalEvent>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(lever_signals)
+                    .with_system(pressure_plate_signals)
+                    .with_system(soul_count_signals)
+                    .after(crate::actor::actor_attack)
+                    .after(crate::soul::collect_souls),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(receive_signals)
+                    .after(lever_signals)
+                    .after(pressure_plate_signals)
+                    .after(soul_count_signals),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(source_visuals)
+                    .after(receive_signals),
+            );
+    }
+}
+
+fn source_sprite(size: Vec2) -> SpriteBundle {
+    SpriteBundle {
+        sprite: Sprite {
+            color: Color::hex("8a8a8a").unwrap(),
+            custom_size: Some(size),
+            ..Default::default()
+        },
+        transform: Transform::from_translation(Vec3::new(0., 0., 0.5)),
+        ..Default::default()
+    }
+}
+
+impl LdtkEntity for LeverBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        _layer_instance: &LayerInstance,
+        _tileset: Option<&Handle<Image>>,
+        _tileset_definition: Option<&TilesetDefinition>,
+        _asset_server: &AssetServer,
+        _texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Self {
+        LeverBundle {
+            source_bundle: SignalSourceBundle {
+                sprite_bundle: source_sprite(Vec2::new(6., 14.)),
+                collider: Collider::cuboid(4., 8.),
+                sensor: Sensor,
+                label: Labeled {
+                    name: String::from("lever"),
+                },
+                source: SignalSource::from_entity_instance(entity_instance, SignalTrigger::Lever),
+            },
+            scythable: Scythable::default(),
+        }
+    }
+}
+
+impl LdtkEntity for SignalSourceBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        _layer_instance: &LayerInstance,
+        _tileset: Option<&Handle<Image>>,
+        _tileset_definition: Option<&TilesetDefinition>,
+        _asset_server: &AssetServer,
+        _texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Self {
+        let size = Vec2::new(entity_instance.width as f32, entity_instance.height as f32);
+
+        let (trigger, visible) = match entity_instance.identifier.as_str() {
+            "PressurePlate" => (SignalTrigger::PressurePlate, true),
+            "SoulSwitch" => (
+                SignalTrigger::SoulCount {
+                    souls: 1,
+                    group: None,
+                },
+                false,
+            ),
+            unknown => panic!("LDtk entity \"{}\" is not a signal source!", unknown),
+        };
+
+        let mut sprite_bundle = source_sprite(size);
+        sprite_bundle.visibility.is_visible = visible;
+
+        SignalSourceBundle {
+            sprite_bundle,
+            collider: Collider::cuboid(size.x / 2., size.y / 2.),
+            sensor: Sensor,
+            label: Labeled {
+                name: entity_instance.identifier.to_lowercase(),
+            },
+            source: SignalSource::from_entity_instance(entity_instance, trigger),
+        }
+    }
+}
+
+fn lever_signals(
+    time: Res<Time>,
+    mut levers: Query<(&mut SignalSource, &mut Scythable)>,
+    mut signal_writer: EventWriter<SignalEvent>,
+) {
+    for (mut source, mut scythable) in &mut levers {
+        // Only written while counting down, so idle levers aren't marked changed every frame
+        if source.cooldown > 0. {
+            source.cooldown -= time.delta_seconds();
+        }
+
+        if scythable.scythed {
+            // One swing hits for several frames, so only count the first
+            if source.cooldown <= 0. {
+                let action = source.flip();
+                source.cooldown = 0.5;
+                signal_writer.send(source.signal(action));
+            }
+
+            scythable.scythed = false;
+            scythable.hit_from = None;
+        }
+    }
+}
+
+fn pressure_plate_signals(
+    mut plates: Query<(&mut SignalSource, &GlobalTransform, &Collider)>,
+    actors: Query<(), With<Actor>>,
+    rapier_context: Res<RapierContext>,
+    mut signal_writer: EventWriter<SignalEvent>,
+) {
+    for (mut source, transform, collider) in &mut plates {
+        if source.trigger != SignalTrigger::PressurePlate {
+            continue;
+        }
+
+        let mut pressed = false;
+        rapier_context.intersections_with_shape(
+            transform.translation().truncate(),
+            0.,
+            collider,
+            QueryFilter::new().exclude_sensors(),
+            |entity| -> bool {
+                pressed = actors.contains(entity);
+                !pressed
+            },
+        );
+
+        if let Some(action) = source.edge_action(pressed) {
+            source.triggered = pressed;
+            signal_writer.send(source.signal(action));
+        }
+    }
+}
+
+fn soul_count_signals(
+    mut switches: Query<&mut SignalSource>,
+    player_query: Query<&SoulWallet, (With<Player>, Changed<SoulWallet>)>,
+    mut signal_writer: EventWriter<SignalEvent>,
+) {
+    for wallet in &player_query {
+        for mut source in &mut switches {
+            let Some(reached) = source.soul_count_reached(wallet) else {
+                continue;
+            };
+
+            // Spending the souls switches it back off
+            if let Some(action) = source.edge_action(reached) {
+                source.triggered = reached;
+                signal_writer.send(source.signal(action));
+            }
+        }
+    }
+}
+
+pub fn receive_signals(
+    time: Res<Time>,
+    mut signal_reader: EventReader<SignalEvent>,
+    mut receivers: Query<&mut SignalReceiver>,
+) {
+    for signal in signal_reader.iter() {
+        for mut receiver in &mut receivers {
+            if signal.targets.contains(&receiver.iid) {
+                receiver.apply(signal.action);
+            }
+        }
+    }
+
+    for mut receiver in &mut receivers {
+        // Only touch receivers with a timer, so Changed<SignalReceiver> stays meaningful
+        if receiver.reset_time.is_some() && receiver.active != receiver.default_active {
+            receiver.tick(time.delta_seconds());
+        }
+    }
+}
+
+fn source_visuals(mut sources: Query<(&SignalSource, &mut Sprite), Changed<SignalSource>>) {
+    for (source, mut sprite) in &mut sources {
+        sprite.color = if source.triggered {
+            Color::hex("d9b866").unwrap()
+        } else {
+            Color::hex("8a8a8a").unwrap()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receiver(default_active: bool, reset_time: Option<f32>) -> SignalReceiver {
+        SignalReceiver {
+            reset_time,
+            ..SignalReceiver::new(String::from("target"), default_active)
+        }
+    }
+
+    fn soul_switch(souls: usize, group: Option<&str>) -> SignalSource {
+        SignalSource {
+            targets: vec![String::from("target")],
+            action: SignalAction::On,
+            trigger: SignalTrigger::SoulCount {
+                souls,
+                group: group.map(String::from),
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn apply_sets_and_toggles() {
+        let mut receiver = receiver(false, None);
+
+        receiver.apply(SignalAction::On);
+        assert!(receiver.active);
+        receiver.apply(SignalAction::On);
+        assert!(receiver.active);
+
+        receiver.apply(SignalAction::Off);
+        assert!(!receiver.active);
+
+        receiver.apply(SignalAction::Toggle);
+        assert!(receiver.active);
+        receiver.apply(SignalAction::Toggle);
+        assert!(!receiver.active);
+    }
+
+    #[test]
+    fn tick_resets_to_default_after_reset_time() {
+        let mut receiver = receiver(false, Some(1.));
+
+        receiver.apply(SignalAction::On);
+        assert!(!receiver.tick(0.6));
+        assert!(receiver.active);
+
+        assert!(receiver.tick(0.6));
+        assert!(!receiver.active);
+
+        // Already back at the default, so there's nothing to reset
+        assert!(!receiver.tick(2.));
+        assert!(!receiver.active);
+    }
+
+    #[test]
+    fn apply_restarts_reset_timer() {
+        let mut receiver = receiver(true, Some(1.));
+
+        receiver.apply(SignalAction::Off);
+        assert!(!receiver.tick(0.8));
+        receiver.apply(SignalAction::Off);
+        assert!(!receiver.tick(0.8));
+        assert!(!receiver.active);
+
+        assert!(receiver.tick(0.3));
+        assert!(receiver.active);
+    }
+
+    #[test]
+    fn tick_without_reset_time_keeps_state() {
+        let mut receiver = receiver(false, None);
+
+        receiver.apply(SignalAction::On);
+        assert!(!receiver.tick(100.));
+        assert!(receiver.active);
+    }
+
+    #[test]
+    fn opposite_swaps_on_and_off() {
+        assert_eq!(SignalAction::On.opposite(), SignalAction::Off);
+        assert_eq!(SignalAction::Off.opposite(), SignalAction::On);
+        assert_eq!(SignalAction::Toggle.opposite(), SignalAction::Toggle);
+    }
+
+    #[test]
+    fn levers_switch_on_off_and_on_again() {
+        let mut lever = SignalSource {
+            targets: vec![String::from("target")],
+            action: SignalAction::On,
+            ..Default::default()
+        };
+        let mut receiver = receiver(false, None);
+
+        for expected in [true, false, true] {
+            let action = lever.flip();
+            assert_eq!(lever.triggered, expected);
+
+            receiver.apply(action);
+            assert_eq!(receiver.active, expected);
+        }
+    }
+
+    #[test]
+    fn toggle_levers_toggle_both_ways() {
+        let mut lever = SignalSource::default();
+
+        assert_eq!(lever.flip(), SignalAction::Toggle);
+        assert_eq!(lever.flip(), SignalAction::Toggle);
+        assert!(!lever.triggered);
+    }
+
+    #[test]
+    fn soul_count_switches_on_and_back_off() {
+        let mut source = soul_switch(3, None);
+        let mut wallet = SoulWallet::default();
+
+        wallet.add(2, None);
+        assert_eq!(source.soul_count_reached(&wallet), Some(false));
+        assert_eq!(source.edge_action(false), None);
+
+        wallet.add(1, None);
+        assert_eq!(source.soul_count_reached(&wallet), Some(true));
+        assert_eq!(source.edge_action(true), Some(SignalAction::On));
+        source.triggered = true;
+        assert_eq!(source.edge_action(true), None);
+
+        assert!(wallet.spend(3, None));
+        assert_eq!(source.soul_count_reached(&wallet), Some(false));
+        assert_eq!(source.edge_action(false), Some(SignalAction::Off));
+    }
+
+    #[test]
+    fn soul_count_only_counts_its_group() {
+        let source = soul_switch(2, Some("blue"));
+        let mut wallet = SoulWallet::default();
+
+        wallet.add(5, None);
+        wallet.add(1, Some("blue"));
+        assert_eq!(source.soul_count_reached(&wallet), Some(false));
+
+        wallet.add(1, Some("blue"));
+        assert_eq!(source.soul_count_reached(&wallet), Some(true));
+    }
+
+    #[test]
+    fn other_triggers_ignore_souls() {
+        let source = SignalSource::default();
+        let mut wallet = SoulWallet::default();
+        wallet.add(10, None);
+
+        assert_eq!(source.soul_count_reached(&wallet), None);
+    }
+}
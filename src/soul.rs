@@ -1,9 +1,14 @@
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 use bevy_rapier2d::prelude::*;
+use std::collections::HashMap;
 
-use crate::loading::AudioAssets;
+use crate::actor::Actor;
+use crate::ghost::{Ghost, GhostBundle};
+use crate::loading::{AudioAssets, SpriteAssets};
+use crate::magnet::SoulMagnet;
 use crate::pickup::PickupEvent;
+use crate::zone::{ForceZone, ZoneEffects};
 use crate::{pickup, GameState};
 use crate::{
     pickup::{Pickup, PickupType},
@@ -13,6 +18,48 @@ use crate::{
 
 pub struct SoulPlugin;
 
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SoulTier {
+    #[default]
+    Small,
+    Large,
+    Golden,
+}
+
+impl SoulTier {
+    pub fn from_name(name: &str) -> Option<SoulTier> {
+        match name.to_lowercase().as_str() {
+            "small" => Some(SoulTier::Small),
+            "large" => Some(SoulTier::Large),
+            "golden" => Some(SoulTier::Golden),
+            _ => None,
+        }
+    }
+
+    pub fn default_value(&self) -> usize {
+        match self {
+            SoulTier::Small => 1,
+            SoulTier::Large => 3,
+            SoulTier::Golden => 5,
+        }
+    }
+
+    pub fn scale(&self) -> f32 {
+        match self {
+            SoulTier::Small => 1.,
+            SoulTier::Large => 1.5,
+            SoulTier::Golden => 1.25,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            SoulTier::Small | SoulTier::Large => Color::WHITE,
+            SoulTier::Golden => Color::rgb(1., 0.85, 0.3),
+        }
+    }
+}
+
 #[derive(Component, Default, Clone)]
 pub struct Soul {
     pub can_move: bool,
@@ -20,6 +67,52 @@ pub struct Soul {
     pub accel: f32,
     pub velocity: Vec2,
     pub from_ghost: bool,
+    pub tier: SoulTier,
+    pub value: usize,
+    pub group: Option<String>,
+}
+
+/// Souls the player has collected since coming through a door. Walking into a neighbouring
+/// room keeps them, so souls from one room can open doors in the next, while every door
+/// starts the player over with an empty wallet
+#[derive(Component, Default, Clone, Debug)]
+pub struct SoulWallet {
+    pub souls: usize,
+    pub groups: HashMap<String, usize>,
+}
+
+impl SoulWallet {
+    pub fn add(&mut self, value: usize, group: Option<&str>) {
+        self.souls += value;
+
+        if let Some(group) = group {
+            *self.groups.entry(group.to_string()).or_default() += value;
+        }
+    }
+
+    pub fn count(&self, group: Option<&str>) -> usize {
+        match group {
+            // Spending ungrouped souls can leave a group count higher than the total
+            Some(group) => self.groups.get(group).copied().unwrap_or(0).min(self.souls),
+            None => self.souls,
+        }
+    }
+
+    pub fn spend(&mut self, value: usize, group: Option<&str>) -> bool {
+        if self.count(group) < value {
+            return false;
+        }
+
+        self.souls -= value;
+
+        if let Some(group) = group {
+            if let Some(group_count) = self.groups.get_mut(group) {
+                *group_count -= value;
+            }
+        }
+
+        true
+    }
 }
 
 #[derive(Clone, Default, Bundle)]
@@ -36,9 +129,47 @@ pub struct SoulBundle {
     pub pickup: Pickup,
 }
 
+/// What happens to a decaying soul once its lifetime runs out
+#[derive(Clone)]
+pub enum SoulFate {
+    Escape,
+    ReturnToGhost { ghost: Ghost, actor: Actor },
+}
+
+/// Optional lifetime for a soul, after which it is lost if not collected
+#[derive(Component, Clone)]
+pub struct SoulDecay {
+    pub lifetime: f32,
+    pub warning_time: f32,
+    pub escape_time: f32,
+    pub escape_speed: f32,
+    pub timer: f32,
+    pub fate: SoulFate,
+}
+
+impl SoulDecay {
+    pub fn new(lifetime: f32, fate: SoulFate) -> Self {
+        SoulDecay {
+            lifetime,
+            warning_time: lifetime.min(2.5),
+            escape_time: 1.5,
+            escape_speed: 80.,
+            timer: 0.,
+            fate,
+        }
+    }
+
+    pub fn expired(&self) -> bool {
+        self.timer >= self.lifetime
+    }
+}
+
 pub struct CollectedSoulEvent {
     pub collector_entity: Entity,
     pub pickup_pos: Vec3,
+    pub tier: SoulTier,
+    pub value: usize,
+    pub group: Option<String>,
 }
 
 impl Plugin for SoulPlugin {
@@ -46,8 +177,14 @@ impl Plugin for SoulPlugin {
         app.add_event::<CollectedSoulEvent>().add_system_set(
             SystemSet::on_update(GameState::Playing)
                 .with_system(soul_movement)
+                .with_system(soul_decay)
                 .with_system(soul_pickups)
                 .after(crate::pickup::check_for_pickups),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(collect_souls)
+                .after(soul_pickups),
         );
     }
 }
@@ -59,12 +196,31 @@ fn soul_pickups(
     audio_assets: Res<AudioAssets>,
 ) {
     for pickup_ev in pickup_reader.iter() {
-        if pickup_ev.pickup_type == pickup::PickupType::Soul {
+        if let pickup::PickupType::Soul { tier, value, group } = &pickup_ev.pickup_type {
             soul_writer.send(CollectedSoulEvent {
                 collector_entity: pickup_ev.collector_entity,
                 pickup_pos: pickup_ev.pickup_position,
+                tier: *tier,
+                value: *value,
+                group: group.clone(),
             });
-            audio.play(audio_assets.pickup.clone());
+
+            if *tier == SoulTier::Small {
+                audio.play(audio_assets.pickup.clone());
+            } else {
+                audio.play(audio_assets.big_pickup.clone());
+            }
+        }
+    }
+}
+
+pub fn collect_souls(
+    mut soul_reader: EventReader<CollectedSoulEvent>,
+    mut wallet_query: Query<&mut SoulWallet>,
+) {
+    for soul_ev in soul_reader.iter() {
+        if let Ok(mut wallet) = wallet_query.get_mut(soul_ev.collector_entity) {
+            wallet.add(soul_ev.value, soul_ev.group.as_deref());
         }
     }
 }
@@ -89,7 +245,11 @@ impl LdtkEntity for SoulBundle {
             accel: 40.,
             velocity: Vec2::ZERO,
             from_ghost: false,
+            tier: SoulTier::Small,
+            value: 0,
+            group: None,
         };
+        let mut value_override = None;
 
         for field in entity_instance.field_instances.iter() {
             match field.identifier.as_str() {
@@ -108,18 +268,43 @@ impl LdtkEntity for SoulBundle {
                         soul.accel = value;
                     }
                 }
+                "Tier" => {
+                    if let FieldValue::String(Some(value)) = &field.value {
+                        if let Some(tier) = SoulTier::from_name(value) {
+                            soul.tier = tier;
+                        } else {
+                            println!("Unknown soul tier \"{}\" on LDtk soul object!", value);
+                        }
+                    }
+                }
+                "Value" => {
+                    if let FieldValue::Int(Some(value)) = field.value {
+                        value_override = Some(value.max(0) as usize);
+                    }
+                }
+                "Group" => {
+                    if let FieldValue::String(value) = &field.value {
+                        soul.group = value.clone().filter(|group| !group.is_empty());
+                    }
+                }
                 unknown => println!("Unknown field \"{}\" on LDtk soul object!", unknown),
             }
         }
 
+        soul.value = value_override.unwrap_or(soul.tier.default_value());
+
         SoulBundle {
             sprite_sheet_bundle: SpriteSheetBundle {
                 texture_atlas: texture_atlas_handle,
-                transform: Transform::from_translation(Vec3::new(0., 0., 1.)),
+                sprite: TextureAtlasSprite {
+                    color: soul.tier.color(),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(Vec3::new(0., 0., 1.))
+                    .with_scale(Vec3::splat(soul.tier.scale())),
                 ..Default::default()
             },
             sprite_animator: crate::sprite_anim::SpriteAnimator::new(0, 3, 4, 0.2, true, true),
-            soul,
             rigidbody: RigidBody::KinematicPositionBased,
             collider: Collider::ball(5.),
             sensor: Sensor,
@@ -132,31 +317,126 @@ impl LdtkEntity for SoulBundle {
                 filter_flags: QueryFilterFlags::EXCLUDE_SENSORS,
                 ..Default::default()
             },
-            pickup: Pickup {
-                pickup_type: Some(PickupType::Soul),
-            },
+            pickup: Pickup::new(PickupType::Soul {
+                tier: soul.tier,
+                value: soul.value,
+                group: soul.group.clone(),
+            }),
+            soul,
+        }
+    }
+}
+
+fn soul_decay(
+    time: Res<Time>,
+    mut soul_query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut SoulDecay,
+            &mut TextureAtlasSprite,
+            Option<&Pickup>,
+        ),
+        With<Soul>,
+    >,
+    mut commands: Commands,
+    sprites: Res<SpriteAssets>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+) {
+    for (entity, mut transform, mut decay, mut sprite, pickup) in &mut soul_query {
+        decay.timer += time.delta_seconds();
+        let remaining = decay.lifetime - decay.timer;
+
+        if remaining > decay.warning_time {
+            continue;
+        }
+
+        if remaining > 0. {
+            // Flicker faster the closer the soul gets to escaping
+            let urgency = 1. - (remaining / decay.warning_time);
+            let flicker = f32::sin(decay.timer * (8. + 24. * urgency)).abs();
+            sprite.color.set_a(0.3 + 0.7 * flicker);
+            continue;
+        }
+
+        match &decay.fate {
+            SoulFate::Escape => {
+                let escape_progress = -remaining / decay.escape_time;
+
+                if escape_progress >= 1. {
+                    commands.entity(entity).despawn_recursive();
+                } else {
+                    if pickup.is_some() {
+                        commands.entity(entity).remove::<Pickup>();
+                    }
+
+                    // Drift up through the ceiling, ignoring collision
+                    transform.translation.y += decay.escape_speed * time.delta_seconds();
+                    sprite.color.set_a(1. - escape_progress);
+                }
+            }
+            SoulFate::ReturnToGhost { ghost, actor } => {
+                commands.entity(entity).despawn_recursive();
+
+                let texture_atlas = TextureAtlas::from_grid(
+                    sprites.texture_ghost.clone(),
+                    Vec2::new(24., 24.),
+                    4,
+                    1,
+                    None,
+                    None,
+                );
+                let texture_atlas_handle = texture_atlases.add(texture_atlas);
+
+                let mut ghost_bundle =
+                    GhostBundle::new(texture_atlas_handle, actor.clone(), ghost.clone());
+                ghost_bundle.sprite_sheet_bundle.transform.translation = transform.translation;
+                ghost_bundle.label.name = String::from("reborn ghost");
+
+                commands.spawn(ghost_bundle);
+            }
         }
     }
 }
 
-fn soul_movement(
+pub fn soul_movement(
     time: Res<Time>,
     mut soul_query: Query<(
         Entity,
-        &Transform,
+        &GlobalTransform,
         &mut Soul,
         &mut KinematicCharacterController,
+        Option<&SoulDecay>,
     )>,
     spike_query: Query<&crate::player::TouchDeath, Without<crate::ghost::Ghost>>,
-    player_query: Query<&Transform, With<crate::player::Player>>,
+    player_query: Query<(&GlobalTransform, Option<&SoulMagnet>), With<crate::player::Player>>,
+    zone_query: Query<(&GlobalTransform, &ForceZone)>,
     rapier_context: Res<RapierContext>,
 ) {
-    for (entity, transform, mut soul, mut controller) in &mut soul_query {
+    for (entity, transform, mut soul, mut controller, decay) in &mut soul_query {
+        if decay.is_some_and(|decay| decay.expired()) {
+            // Expired souls are moved by soul_decay instead
+            continue;
+        }
+
+        // Gusts carry souls along on top of wherever they want to go
+        let wind = ZoneEffects::at(transform.translation().truncate(), &zone_query).wind;
+
+        let magnet_pull = player_query
+            .get_single()
+            .ok()
+            .and_then(|(player_transform, magnet)| {
+                magnet?.pull_on(
+                    player_transform.translation().truncate(),
+                    transform.translation().truncate(),
+                )
+            });
+
         if soul.can_move {
-            if let Ok(player_transform) = player_query.get_single() {
+            if let Ok((player_transform, _)) = player_query.get_single() {
                 let comfortable_distance = 256.;
                 let dir_from_player =
-                    (transform.translation - player_transform.translation).truncate();
+                    (transform.translation() - player_transform.translation()).truncate();
                 let dist_from_player = dir_from_player.length();
                 let flee_priority = (comfortable_distance / dist_from_player).powi(3);
                 let flee_vec = dir_from_player.normalize_or_zero();
@@ -168,7 +448,7 @@ fn soul_movement(
                     .exclude_sensors()
                     .exclude_collider(entity);
                 let shape = Collider::ball(4.9);
-                let shape_pos = transform.translation.truncate();
+                let shape_pos = transform.translation().truncate();
 
                 let down_cast = rapier_context.cast_shape(
                     shape_pos,
@@ -259,6 +539,13 @@ fn soul_movement(
                 //println!("flee: {0}, spacing: {1}, height fix: {2}", flee_priority, centering_priority, height_priority);
                 //println!("flee vec: {0} spacing vec: {1}", flee_vec, centering_vec);
 
+                // Being pulled in by a magnet overrides the urge to flee
+                let flee_vec = if magnet_pull.is_some() {
+                    Vec2::ZERO
+                } else {
+                    flee_vec
+                };
+
                 let total_vec = (idle_vec
                     + (flee_priority * flee_vec)
                     + (centering_priority * centering_vec)
@@ -281,16 +568,20 @@ fn soul_movement(
                     soul.velocity.x = -0.5 * soul.velocity.x;
                 }
 
-                controller.translation = Some(soul.velocity * time.delta_seconds());
+                controller.translation = Some(
+                    (soul.velocity + magnet_pull.unwrap_or(Vec2::ZERO) + wind)
+                        * time.delta_seconds(),
+                );
             }
         } else {
             controller.translation = Some(
                 time.delta_seconds()
-                    * Vec2::new(
+                    * (Vec2::new(
                         f32::sin(time.elapsed_seconds()),
                         f32::cos(time.elapsed_seconds()),
-                    )
-                    * 2.,
+                    ) * 2.
+                        + magnet_pull.unwrap_or(Vec2::ZERO)
+                        + wind),
             );
         }
     }
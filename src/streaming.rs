@@ -0,0 +1,207 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::actor::ActorStatus;
+use crate::door::LevelEntry;
+use crate::gate::KeyRing;
+use crate::player::Player;
+use crate::soul::SoulWallet;
+use crate::world::{ChangeLevelEvent, ReloadWorldEvent};
+use crate::GameState;
+
+/// Walks the player between neighbouring levels without respawning them
+pub struct StreamingPlugin;
+
+/// How the player entered the room they are in, so dying can put them back the same way
+#[derive(Resource, Default, Debug)]
+pub struct RoomEntry {
+    pub level: Option<usize>,
+    pub position: Vec3,
+    pub wallet: SoulWallet,
+    pub key_ring: KeyRing,
+}
+
+impl Plugin for StreamingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RoomEntry>()
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(keep_one_player)
+                    .with_system(respawn_player_through_doors)
+                    .after(crate::player::player_win),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(follow_player_between_levels)
+                    .after(keep_one_player),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(record_room_entry)
+                    .after(follow_player_between_levels)
+                    .after(crate::door::place_player_at_entry),
+            )
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing).with_system(restore_room_entry),
+            )
+            .add_system_set(SystemSet::on_update(GameState::Paused).with_system(restore_room_entry));
+    }
+}
+
+/// Every loaded level spawns its own player, keep the one already playing or the selected level's
+fn keep_one_player(
+    mut commands: Commands,
+    players: Query<(Entity, &Worldly, ChangeTrackers<Player>)>,
+    level_selection: Res<LevelSelection>,
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+) {
+    if players.iter().count() <= 1 {
+        return;
+    }
+
+    let selected_player_iids: Vec<String> = ldtk_query
+        .get_single()
+        .ok()
+        .and_then(|handle| ldtk_assets.get(handle))
+        .and_then(|ldtk| crate::world::selected_level(&ldtk.project, &level_selection))
+        .map(|level| {
+            level
+                .layer_instances
+                .iter()
+                .flatten()
+                .flat_map(|layer| layer.entity_instances.iter())
+                .map(|entity_instance| entity_instance.iid.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let keeper = players
+        .iter()
+        .find(|(_, _, tracker)| !tracker.is_added())
+        .or_else(|| {
+            players
+                .iter()
+                .find(|(_, worldly, _)| selected_player_iids.contains(&worldly.entity_iid))
+        })
+        .or_else(|| players.iter().next())
+        .map(|(entity, _, _)| entity);
+
+    for (entity, _, _) in &players {
+        if Some(entity) != keeper {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Selects whichever level the player is standing in, its neighbours load in around it
+fn follow_player_between_levels(
+    player_query: Query<&Transform, With<Player>>,
+    mut change_events: EventReader<ChangeLevelEvent>,
+    mut level_selection: ResMut<LevelSelection>,
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+) {
+    // Going through a door, the player is about to be replaced by the new level's one
+    if change_events.iter().count() > 0 {
+        return;
+    }
+
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+
+    let Some(ldtk) = ldtk_query.get_single().ok().and_then(|handle| ldtk_assets.get(handle)) else {
+        return;
+    };
+
+    // The player is worldly, so their transform is already in world space
+    let position = player_transform.translation.truncate();
+
+    if let Some(index) = crate::world::level_index_at(&ldtk.project, position) {
+        if *level_selection != LevelSelection::Index(index) {
+            println!("entering {}", ldtk.project.levels[index].identifier);
+            *level_selection = LevelSelection::Index(index);
+        }
+    }
+}
+
+/// Doors send the player somewhere new, so they start fresh from that level's own player
+fn respawn_player_through_doors(
+    mut commands: Commands,
+    mut change_events: EventReader<ChangeLevelEvent>,
+    players: Query<Entity, With<Player>>,
+    levels: Query<(Entity, &Handle<LdtkLevel>)>,
+    ldtk_levels: Res<Assets<LdtkLevel>>,
+) {
+    for ev in change_events.iter() {
+        let Some(reference) = &ev.level else {
+            continue;
+        };
+
+        for entity in &players {
+            commands.entity(entity).despawn_recursive();
+        }
+
+        // The target may already be loaded as a neighbour, and then it wouldn't spawn its player
+        for (entity, handle) in &levels {
+            if let Some(ldtk_level) = ldtk_levels.get(handle) {
+                let level = &ldtk_level.level;
+                if level.identifier == *reference || level.iid == *reference {
+                    commands.entity(entity).insert(Respawn);
+                }
+            }
+        }
+    }
+}
+
+pub fn record_room_entry(
+    player_query: Query<(&Transform, &SoulWallet, &KeyRing, ChangeTrackers<Player>)>,
+    level_selection: Res<LevelSelection>,
+    level_entry: Res<LevelEntry>,
+    mut room_entry: ResMut<RoomEntry>,
+) {
+    let LevelSelection::Index(index) = *level_selection else {
+        return;
+    };
+
+    for (transform, wallet, key_ring, tracker) in &player_query {
+        // Wait until the player has been moved to the door they came through
+        if level_entry.door.is_some() {
+            continue;
+        }
+
+        if tracker.is_added() || room_entry.level != Some(index) {
+            *room_entry = RoomEntry {
+                level: Some(index),
+                position: transform.translation,
+                wallet: wallet.clone(),
+                key_ring: key_ring.clone(),
+            };
+        }
+    }
+}
+
+/// The level respawns around the player, who goes back to where they entered it
+fn restore_room_entry(
+    mut reload_events: EventReader<ReloadWorldEvent>,
+    room_entry: Res<RoomEntry>,
+    mut player_query: Query<
+        (&mut Transform, &mut ActorStatus, &mut SoulWallet, &mut KeyRing),
+        With<Player>,
+    >,
+) {
+    let reloaded = reload_events.iter().count() > 0;
+
+    if !reloaded || room_entry.level.is_none() {
+        return;
+    }
+
+    for (mut transform, mut status, mut wallet, mut key_ring) in &mut player_query {
+        transform.translation = room_entry.position;
+        status.velocity = Vec2::ZERO;
+        status.attack_direction = None;
+        *wallet = room_entry.wallet.clone();
+        *key_ring = room_entry.key_ring.clone();
+    }
+}
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+
+use crate::actor::Actor;
+use crate::harvest::Granary;
+use crate::player::Player;
+use crate::save::SaveFile;
+use crate::GameState;
+
+pub struct UpgradePlugin;
+
+/// Permanent improvements to the player bought with grain in the shop
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum Upgrade {
+    AttackRange,
+    MoveSpeed,
+    AirJump,
+}
+
+impl Upgrade {
+    pub const ALL: [Upgrade; 3] = [Upgrade::AttackRange, Upgrade::MoveSpeed, Upgrade::AirJump];
+
+    /// Key the upgrade's level is saved under
+    pub fn name(&self) -> &'static str {
+        match self {
+            Upgrade::AttackRange => "AttackRange",
+            Upgrade::MoveSpeed => "MoveSpeed",
+            Upgrade::AirJump => "AirJump",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Upgrade::AttackRange => "Reach",
+            Upgrade::MoveSpeed => "Speed",
+            Upgrade::AirJump => "Air Jump",
+        }
+    }
+
+    pub fn max_level(&self) -> usize {
+        match self {
+            Upgrade::AttackRange | Upgrade::MoveSpeed => 3,
+            Upgrade::AirJump => 2,
+        }
+    }
+
+    /// Grain needed to buy the next level, each level costing more than the last
+    pub fn price(&self, level: usize) -> Option<usize> {
+        let base = match self {
+            Upgrade::AttackRange | Upgrade::MoveSpeed => 5,
+            Upgrade::AirJump => 15,
+        };
+
+        (level < self.max_level()).then_some(base * (level + 1))
+    }
+
+    /// Applies on top of the stats the player was given in LDtk
+    pub fn apply(&self, level: usize, actor: &mut Actor) {
+        match self {
+            Upgrade::AttackRange => actor.attack_range += 4. * level as f32,
+            Upgrade::MoveSpeed => actor.move_speed *= 1. + 0.1 * level as f32,
+            Upgrade::AirJump => actor.air_jumps = actor.air_jumps.max(level),
+        }
+    }
+
+    pub fn level(&self, save_file: &SaveFile) -> usize {
+        save_file.upgrade_level(self.name()).min(self.max_level())
+    }
+
+    pub fn affordable(&self, grain: usize, save_file: &SaveFile) -> bool {
+        self.price(self.level(save_file))
+            .is_some_and(|price| price <= grain)
+    }
+
+    /// Whether there's anything in the shop the player could buy right now
+    pub fn any_affordable(grain: usize, save_file: &SaveFile) -> bool {
+        Upgrade::ALL
+            .iter()
+            .any(|upgrade| upgrade.affordable(grain, save_file))
+    }
+
+    /// Spends the grain and saves the new level, if the player can afford it
+    pub fn buy(&self, granary: &mut Granary, save_file: &mut SaveFile) -> bool {
+        let level = self.level(save_file);
+
+        match self.price(level) {
+            Some(price) if granary.spend(price) => {
+                save_file.set_upgrade_level(self.name(), level + 1);
+                save_file.grain = granary.grain;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Plugin for UpgradePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(apply_upgrades)
+                .before(crate::player::player_inputs),
+        );
+    }
+}
+
+fn apply_upgrades(mut player_query: Query<&mut Actor, Added<Player>>, save_file: Res<SaveFile>) {
+    for mut actor in &mut player_query {
+        for upgrade in Upgrade::ALL {
+            upgrade.apply(upgrade.level(&save_file), &mut actor);
+        }
+    }
+}
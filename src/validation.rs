@@ -0,0 +1,464 @@
+use bevy_ecs_ldtk::ldtk::{LdtkJson, Level};
+use bevy_ecs_ldtk::prelude::{EntityInstance, FieldValue};
+use std::collections::VecDeque;
+
+use crate::background::LevelBackgrounds;
+use crate::door::{door_link_errors, level_doors};
+use crate::progression::LevelGraph;
+use crate::soul::SoulTier;
+
+/// Fields the game reads for each LDtk entity, anything else is most likely a typo
+const KNOWN_FIELDS: &[(&str, &[&str])] = &[
+    (
+        "Player",
+        &[
+            "Speed",
+            "Drag",
+            "Acceleration",
+            "Decceleration",
+            "UpGravity",
+            "DownGravity",
+            "JumpPower",
+            "JumpTime",
+            "CanAttack",
+            "AttackTime",
+            "AttackRange",
+            "SoulMagnet",
+            "MagnetRadius",
+        ],
+    ),
+    (
+        "Ghost",
+        &[
+            "Speed",
+            "Drag",
+            "Acceleration",
+            "Decceleration",
+            "UpGravity",
+            "DownGravity",
+            "JumpPower",
+            "JumpTime",
+            "AttackTime",
+            "StartLeft",
+            "SoulLifetime",
+            "SoulReturns",
+            "SoulGroup",
+        ],
+    ),
+    (
+        "Soul",
+        &["Move", "Speed", "Acceleration", "Tier", "Value", "Group"],
+    ),
+    (
+        "Door",
+        &[
+            "TargetLevel",
+            "SoulsNeeded",
+            "WinGame",
+            "EntryDoor",
+            "Name",
+            "Mode",
+            "SoulGroup",
+            "GrainPrice",
+            "StartActive",
+            "ResetTime",
+        ],
+    ),
+    ("Wheat", &["RegrowTime", "Grain"]),
+    ("Key", &["KeyId"]),
+    ("Health", &["Amount"]),
+    ("Secret", &[]),
+    ("SecretArea", &[]),
+    ("Gate", &["KeyId", "StartActive", "ResetTime"]),
+    ("Lever", &["Targets", "Target", "Action"]),
+    ("PressurePlate", &["Targets", "Target", "Action"]),
+    (
+        "SoulSwitch",
+        &["Targets", "Target", "Action", "Souls", "SoulGroup"],
+    ),
+    (
+        "Platform",
+        &["TravelX", "TravelY", "Speed", "StartActive", "ResetTime"],
+    ),
+    ("SpikeTrap", &["StartActive", "ResetTime"]),
+    ("Checkpoint", &[]),
+    ("Wind", &["Angle", "Strength"]),
+    ("Water", &["Buoyancy", "Drag"]),
+    ("LowGravity", &["GravityScale"]),
+];
+
+/// Everything wrong with the project that would break a level at runtime, empty if it's fine
+pub fn validate_project(project: &LdtkJson) -> Vec<String> {
+    let mut errors = door_link_errors(project);
+
+    for level in project.levels.iter() {
+        errors.extend(unknown_field_errors(level));
+        errors.extend(soul_count_errors(level));
+        errors.extend(background_errors(level));
+
+        if !level_entities(level).any(|entity_instance| entity_instance.identifier == "Player") {
+            errors.push(format!("{} has no Player entity", level.identifier));
+        }
+    }
+
+    errors.extend(unreachable_level_errors(project));
+
+    errors
+}
+
+fn level_entities(level: &Level) -> impl Iterator<Item = &EntityInstance> {
+    level
+        .layer_instances
+        .iter()
+        .flatten()
+        .flat_map(|layer| layer.entity_instances.iter())
+}
+
+fn unknown_field_errors(level: &Level) -> Vec<String> {
+    let mut errors = Vec::new();
+
+    for entity_instance in level_entities(level) {
+        let Some((_, fields)) = KNOWN_FIELDS
+            .iter()
+            .find(|(identifier, _)| *identifier == entity_instance.identifier)
+        else {
+            errors.push(format!(
+                "{} has a {} entity, which the game doesn't know about",
+                level.identifier, entity_instance.identifier
+            ));
+            continue;
+        };
+
+        for field in entity_instance.field_instances.iter() {
+            if !fields.contains(&field.identifier.as_str()) {
+                errors.push(format!(
+                    "{} entity in {} has unknown field \"{}\"",
+                    entity_instance.identifier, level.identifier, field.identifier
+                ));
+            }
+        }
+    }
+
+    errors
+}
+
+/// Souls a single placed soul or ghost is worth, and the group it counts towards
+fn soul_value(entity_instance: &EntityInstance) -> Option<(usize, Option<String>)> {
+    let group_field = match entity_instance.identifier.as_str() {
+        "Soul" => "Group",
+        "Ghost" => "SoulGroup",
+        _ => return None,
+    };
+
+    let mut tier = SoulTier::Small;
+    let mut value = None;
+    let mut group = None;
+
+    for field in entity_instance.field_instances.iter() {
+        match (field.identifier.as_str(), &field.value) {
+            ("Tier", FieldValue::String(Some(name))) => {
+                tier = SoulTier::from_name(name).unwrap_or(tier);
+            }
+            ("Value", FieldValue::Int(Some(override_value))) => {
+                value = Some((*override_value).max(0) as usize);
+            }
+            (identifier, FieldValue::String(name)) if identifier == group_field => {
+                group = name.clone().filter(|group| !group.is_empty());
+            }
+            _ => (),
+        }
+    }
+
+    // Ghosts have neither field, so they count as the single small soul they release
+    Some((value.unwrap_or(tier.default_value()), group))
+}
+
+fn soul_count_errors(level: &Level) -> Vec<String> {
+    let souls: Vec<(usize, Option<String>)> =
+        level_entities(level).filter_map(soul_value).collect();
+
+    level_doors(level)
+        .iter()
+        // Doors with a grain price can always be bought instead
+        .filter(|door| door.required_souls > 0 && door.grain_price.is_none())
+        .filter_map(|door| {
+            let available: usize = souls
+                .iter()
+                .filter(|(_, group)| door.soul_group.is_none() || *group == door.soul_group)
+                .map(|(value, _)| value)
+                .sum();
+
+            if available < door.required_souls {
+                Some(format!(
+                    "Door in {} needs {} souls{} but only {} can be collected there",
+                    level.identifier,
+                    door.required_souls,
+                    door.soul_group
+                        .as_ref()
+                        .map(|group| format!(" from group \"{}\"", group))
+                        .unwrap_or_default(),
+                    available
+                ))
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+fn background_errors(level: &Level) -> Vec<String> {
+    let backgrounds = LevelBackgrounds::from_level(level);
+
+    if backgrounds.paths.len() == backgrounds.factors.len() {
+        return Vec::new();
+    }
+
+    vec![format!(
+        "{} has {} backgrounds but {} parallax factors",
+        level.identifier,
+        backgrounds.paths.len(),
+        backgrounds.factors.len()
+    )]
+}
+
+/// Levels that can't be reached from the first one, through doors or by walking across
+fn unreachable_level_errors(project: &LdtkJson) -> Vec<String> {
+    let graph = LevelGraph::from_project(project);
+    let mut reached = vec![false; project.levels.len()];
+    let mut queue = VecDeque::new();
+
+    if !project.levels.is_empty() {
+        queue.push_back(0);
+    }
+
+    while let Some(index) = queue.pop_front() {
+        if reached[index] {
+            continue;
+        }
+
+        reached[index] = true;
+        queue.extend(graph.next_levels(index).iter().copied());
+        queue.extend(crate::world::neighbour_level_indices(project, index));
+    }
+
+    project
+        .levels
+        .iter()
+        .zip(reached)
+        .filter(|(_, reached)| !reached)
+        .map(|(level, _)| format!("{} can't be reached from the first level", level.identifier))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs_ldtk::ldtk::FieldInstance;
+
+    fn world() -> LdtkJson {
+        serde_json::from_str(include_str!("../assets/levels/World.ldtk")).unwrap()
+    }
+
+    fn field(identifier: &str, value: FieldValue) -> FieldInstance {
+        FieldInstance {
+            identifier: String::from(identifier),
+            tile: None,
+            field_instance_type: String::new(),
+            value,
+            def_uid: 0,
+            real_editor_values: Vec::new(),
+        }
+    }
+
+    fn entity(identifier: &str, fields: Vec<FieldInstance>) -> EntityInstance {
+        EntityInstance {
+            identifier: String::from(identifier),
+            field_instances: fields,
+            ..Default::default()
+        }
+    }
+
+    fn door_to(target: &str, souls: i32) -> EntityInstance {
+        entity(
+            "Door",
+            vec![
+                field(
+                    "TargetLevel",
+                    FieldValue::String(Some(String::from(target))),
+                ),
+                field("SoulsNeeded", FieldValue::Int(Some(souls))),
+            ],
+        )
+    }
+
+    fn winning_door() -> EntityInstance {
+        entity("Door", vec![field("WinGame", FieldValue::Bool(true))])
+    }
+
+    /// A level holding the entities, with a player added unless it's left out on purpose
+    fn level(identifier: &str, mut entities: Vec<EntityInstance>) -> Level {
+        entities.push(entity("Player", Vec::new()));
+        level_without_player(identifier, entities)
+    }
+
+    fn level_without_player(identifier: &str, entities: Vec<EntityInstance>) -> Level {
+        let mut layer = world().levels[0].layer_instances.as_ref().unwrap()[0].clone();
+        layer.entity_instances = entities;
+
+        Level {
+            identifier: String::from(identifier),
+            iid: format!("{}-iid", identifier),
+            layer_instances: Some(vec![layer]),
+            ..Default::default()
+        }
+    }
+
+    /// Small hand-built projects borrow everything but their levels from the real one
+    fn project(levels: Vec<Level>) -> LdtkJson {
+        let mut project = world();
+        project.levels = levels;
+        project
+    }
+
+    #[test]
+    fn world_is_valid() {
+        assert_eq!(validate_project(&world()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn linked_levels_are_valid() {
+        let project = project(vec![
+            level("Start", vec![door_to("End", 1), entity("Soul", Vec::new())]),
+            level("End", vec![winning_door()]),
+        ]);
+
+        assert_eq!(validate_project(&project), Vec::<String>::new());
+    }
+
+    #[test]
+    fn missing_players_are_reported() {
+        let project = project(vec![level_without_player("Start", vec![winning_door()])]);
+
+        assert_eq!(
+            validate_project(&project),
+            vec![String::from("Start has no Player entity")]
+        );
+    }
+
+    #[test]
+    fn unknown_fields_and_entities_are_reported() {
+        let level = level(
+            "Start",
+            vec![
+                entity("Soul", vec![field("Colour", FieldValue::Int(Some(1)))]),
+                entity("Dragon", Vec::new()),
+            ],
+        );
+
+        let errors = unknown_field_errors(&level);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].contains("unknown field \"Colour\""));
+        assert!(errors[1].contains("Dragon entity"));
+    }
+
+    #[test]
+    fn soul_counts_include_ghosts_and_tiers() {
+        let large_soul = entity(
+            "Soul",
+            vec![field(
+                "Tier",
+                FieldValue::String(Some(String::from("Large"))),
+            )],
+        );
+
+        let enough = level(
+            "Start",
+            vec![
+                door_to("End", 4),
+                large_soul.clone(),
+                entity("Ghost", Vec::new()),
+            ],
+        );
+        assert!(soul_count_errors(&enough).is_empty());
+
+        let short = level("Start", vec![door_to("End", 5), large_soul]);
+        let errors = soul_count_errors(&short);
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains("needs 5 souls but only 3"));
+    }
+
+    #[test]
+    fn soul_counts_respect_groups_and_grain_prices() {
+        let mut grouped_door = door_to("End", 1);
+        grouped_door.field_instances.push(field(
+            "SoulGroup",
+            FieldValue::String(Some(String::from("blue"))),
+        ));
+        let ungrouped = level(
+            "Start",
+            vec![grouped_door.clone(), entity("Soul", Vec::new())],
+        );
+        assert!(soul_count_errors(&ungrouped)[0].contains("from group \"blue\""));
+
+        let blue_soul = entity(
+            "Soul",
+            vec![field(
+                "Group",
+                FieldValue::String(Some(String::from("blue"))),
+            )],
+        );
+        assert!(soul_count_errors(&level("Start", vec![grouped_door, blue_soul])).is_empty());
+
+        let mut bought_door = door_to("End", 10);
+        bought_door
+            .field_instances
+            .push(field("GrainPrice", FieldValue::Int(Some(5))));
+        assert!(soul_count_errors(&level("Start", vec![bought_door])).is_empty());
+    }
+
+    #[test]
+    fn backgrounds_need_a_factor_each() {
+        let mut level = level("Start", Vec::new());
+        let paths = vec![
+            Some(String::from("far.png")),
+            Some(String::from("near.png")),
+        ];
+        level.field_instances = vec![
+            field("Backgrounds", FieldValue::Strings(paths)),
+            field("ParallaxFactors", FieldValue::Floats(vec![Some(0.1)])),
+        ];
+        assert_eq!(
+            background_errors(&level),
+            vec![String::from(
+                "Start has 2 backgrounds but 1 parallax factors"
+            )]
+        );
+
+        level.field_instances[1] = field(
+            "ParallaxFactors",
+            FieldValue::Floats(vec![Some(0.1), Some(0.4)]),
+        );
+        assert!(background_errors(&level).is_empty());
+    }
+
+    #[test]
+    fn unreachable_levels_are_reported() {
+        let mut project = project(vec![
+            level("Start", vec![door_to("Middle", 0)]),
+            level("Middle", vec![winning_door()]),
+            level("Hidden", vec![winning_door()]),
+        ]);
+        assert_eq!(
+            unreachable_level_errors(&project),
+            vec![String::from("Hidden can't be reached from the first level")]
+        );
+
+        // Walking across from a neighbouring level counts as reaching it
+        for (index, level) in project.levels.iter_mut().enumerate() {
+            level.world_x = index as i32 * 256;
+            level.px_wid = 256;
+            level.px_hei = 256;
+        }
+        assert!(unreachable_level_errors(&project).is_empty());
+    }
+}
@@ -1,10 +1,16 @@
 use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::{LdtkJson, Level};
 use bevy_ecs_ldtk::prelude::*;
-use bevy_pkv::PkvStore;
 use bevy_rapier2d::prelude::*;
 use std::collections::{HashMap, HashSet};
 
-use crate::{actor::Scythable, door, GameState};
+use crate::collision::merge_cells;
+use crate::harvest::Granary;
+use crate::loading::LevelAssets;
+use crate::progression::LevelGraph;
+use crate::save::{LevelClock, SaveFile};
+use crate::upgrade::Upgrade;
+use crate::{door, door::LevelEntry, GameState};
 
 pub struct WorldPlugin;
 
@@ -16,7 +22,9 @@ pub struct Labeled {
 pub struct ReloadWorldEvent;
 
 pub struct ChangeLevelEvent {
-    pub index: usize,
+    /// LDtk identifier or IID of the level to go to
+    pub level: Option<String>,
+    pub entry_door: Option<String>,
     pub completed: bool,
     pub win_game: bool,
 }
@@ -24,6 +32,12 @@ pub struct ChangeLevelEvent {
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(LevelSelection::Index(0))
+            .insert_resource(LdtkSettings {
+                level_spawn_behavior: LevelSpawnBehavior::UseWorldTranslation {
+                    load_level_neighbors: true,
+                },
+                ..Default::default()
+            })
             .add_event::<ReloadWorldEvent>()
             .add_event::<ChangeLevelEvent>()
             .add_plugin(LdtkPlugin)
@@ -35,18 +49,40 @@ impl Plugin for WorldPlugin {
             })
             .add_system_set(SystemSet::on_enter(GameState::Playing).with_system(setup_world))
             .add_system_set(SystemSet::on_update(GameState::Playing).with_system(switch_level))
-            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(reload_level))
+            .add_system_set(
+                SystemSet::on_update(GameState::Playing)
+                    .with_system(restart_key)
+                    .with_system(reload_level.after(restart_key)),
+            )
             .add_system_set(SystemSet::on_update(GameState::Paused).with_system(reload_level))
-            .add_system_set(SystemSet::on_update(GameState::Playing).with_system(cut_wheat))
             .add_system_set(SystemSet::on_exit(GameState::Playing).with_system(cleanup_world))
             .add_system(spawn_wall_collision)
+            .add_system(spawn_spike_collision)
             .register_ldtk_entity::<crate::player::PlayerBundle>("Player")
             .register_ldtk_entity::<crate::ghost::GhostBundle>("Ghost")
             .register_ldtk_entity::<crate::soul::SoulBundle>("Soul")
             .register_ldtk_entity::<crate::door::DoorBundle>("Door")
-            .register_ldtk_entity::<WheatBundle>("Wheat")
+            .register_ldtk_entity::<crate::harvest::WheatBundle>("Wheat")
+            .register_ldtk_entity::<crate::pickup::PickupBundle>("Key")
+            .register_ldtk_entity::<crate::pickup::PickupBundle>("Health")
+            .register_ldtk_entity::<crate::pickup::PickupBundle>("Secret")
+            .register_ldtk_entity::<crate::gate::GateBundle>("Gate")
+            .register_ldtk_entity::<crate::signal::LeverBundle>("Lever")
+            .register_ldtk_entity::<crate::signal::SignalSourceBundle>("PressurePlate")
+            .register_ldtk_entity::<crate::signal::SignalSourceBundle>("SoulSwitch")
+            .register_ldtk_entity::<crate::mechanism::MovingPlatformBundle>("Platform")
+            .register_ldtk_entity::<crate::mechanism::SpikeTrapBundle>("SpikeTrap")
+            .register_ldtk_entity::<crate::checkpoint::CheckpointBundle>("Checkpoint")
+            .register_ldtk_entity::<crate::zone::ForceZoneBundle>("Wind")
+            .register_ldtk_entity::<crate::zone::ForceZoneBundle>("Water")
+            .register_ldtk_entity::<crate::zone::ForceZoneBundle>("LowGravity")
+            .register_ldtk_entity::<crate::secret::SecretAreaBundle>("SecretArea")
             .register_ldtk_int_cell::<WallBundle>(1)
-            .register_ldtk_int_cell::<SpikeBundle>(2);
+            .register_ldtk_int_cell::<SpikeBundle>(2)
+            .register_ldtk_int_cell::<crate::breakable::BreakableBundle>(3)
+            .register_ldtk_int_cell::<SurfaceBundle>(4)
+            .register_ldtk_int_cell::<SurfaceBundle>(5)
+            .register_ldtk_int_cell::<SurfaceBundle>(6);
 
         #[cfg(debug_assertions)]
         {
@@ -57,41 +93,27 @@ impl Plugin for WorldPlugin {
     }
 }
 
-pub struct SaveData;
-
-impl SaveData {
-    fn get_level_key(level: usize) -> String {
-        String::from("Level") + level.to_string().as_str() + "Completed"
-    }
-
-    pub fn has_completed_level(data_store: &PkvStore, level: usize) -> bool {
-        data_store
-            .get::<bool>(SaveData::get_level_key(level).as_str())
-            .unwrap_or(false)
-    }
-
-    pub fn mark_level_complete(data_store: &mut PkvStore, level: usize) {
-        println!("completed level {}", level);
-        data_store
-            .set::<bool>(SaveData::get_level_key(level).as_str(), &true)
-            .unwrap();
-    }
-}
-
 fn cleanup_world(mut commands: Commands, query: Query<Entity, Without<OrthographicProjection>>) {
     for entity in query.iter() {
         commands.entity(entity).despawn();
     }
 }
 
+/// Everything that restarts with the level listens for the event, never the key itself
+fn restart_key(input: Res<Input<KeyCode>>, mut reload_writer: EventWriter<ReloadWorldEvent>) {
+    if input.just_pressed(KeyCode::R) {
+        reload_writer.send(ReloadWorldEvent);
+    }
+}
+
 fn reload_level(
     mut commands: Commands,
     level_query: Query<Entity, With<Handle<LdtkLevel>>>,
-    input: Res<Input<KeyCode>>,
-    reload_event_listener: EventReader<ReloadWorldEvent>,
+    mut reload_event_listener: EventReader<ReloadWorldEvent>,
     souls_query: Query<(Entity, &crate::soul::Soul)>,
+    ghosts_query: Query<(Entity, &crate::ghost::Ghost)>,
 ) {
-    if !reload_event_listener.is_empty() || input.just_pressed(KeyCode::R) {
+    if reload_event_listener.iter().count() > 0 {
         println!("reloading level");
         for level_entity in &level_query {
             commands.entity(level_entity).insert(Respawn);
@@ -102,57 +124,180 @@ fn reload_level(
                 commands.entity(entity).despawn_recursive();
             }
         }
+
+        for (entity, ghost) in &ghosts_query {
+            if ghost.from_soul {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
     }
 }
 
-fn switch_level(
+pub fn switch_level(
     mut level_selection: ResMut<LevelSelection>,
+    mut level_entry: ResMut<LevelEntry>,
     mut change_event_listener: EventReader<ChangeLevelEvent>,
-    mut data_store: ResMut<PkvStore>,
+    mut save_file: ResMut<SaveFile>,
+    level_clock: Res<LevelClock>,
+    granary: Res<Granary>,
     mut app_state: ResMut<State<GameState>>,
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
 ) {
     for ev in change_event_listener.iter() {
         if ev.completed {
             if let LevelSelection::Index(index) = *level_selection {
-                SaveData::mark_level_complete(&mut data_store, index);
+                save_file.complete_level(index, level_clock.elapsed);
             }
         }
 
         if ev.win_game {
             app_state.set(GameState::WinScreen);
-        } else {
-            *level_selection = LevelSelection::Index(ev.index);
+        } else if let Some(reference) = &ev.level {
+            let project = &ldtk_assets
+                .get(ldtk_query.single())
+                .expect("LDtk project should be loaded by this point")
+                .project;
+
+            // Door links are checked when the project loads, so this should always be found
+            let index = find_level_index(project, reference)
+                .unwrap_or_else(|| panic!("Level \"{}\" does not exist", reference));
+
+            level_entry.door = ev.entry_door.clone();
+            *level_selection = LevelSelection::Index(index);
+
+            // Stop at the shop on the way if there's grain to spend, the next level loads behind it
+            if ev.completed && Upgrade::any_affordable(granary.grain, &save_file) {
+                app_state
+                    .push(GameState::Shop)
+                    .unwrap_or_else(|error| println!("couldn't open the shop: {:?}", error));
+            }
         }
     }
 }
 
-fn test_switch_level(mut level_selection: ResMut<LevelSelection>, input: Res<Input<KeyCode>>) {
-    if input.just_pressed(KeyCode::Key1) {
-        *level_selection = LevelSelection::Index(0);
-    } else if input.just_pressed(KeyCode::Key2) {
-        *level_selection = LevelSelection::Index(1);
-    } else if input.just_pressed(KeyCode::Key3) {
-        *level_selection = LevelSelection::Index(2);
-    } else if input.just_pressed(KeyCode::Key4) {
-        *level_selection = LevelSelection::Index(3);
-    } else if input.just_pressed(KeyCode::Key5) {
-        *level_selection = LevelSelection::Index(4);
-    } else if input.just_pressed(KeyCode::Key6) {
-        *level_selection = LevelSelection::Index(5);
-    } else if input.just_pressed(KeyCode::Key7) {
-        *level_selection = LevelSelection::Index(6);
-    } else if input.just_pressed(KeyCode::Key8) {
-        *level_selection = LevelSelection::Index(7);
-    } else if input.just_pressed(KeyCode::Key9) {
-        *level_selection = LevelSelection::Index(8);
-    } else if input.just_pressed(KeyCode::Key0) {
-        *level_selection = LevelSelection::Index(9);
+/// Finds a level in the project by its identifier or IID
+pub fn find_level<'a>(project: &'a LdtkJson, reference: &str) -> Option<&'a Level> {
+    project
+        .levels
+        .iter()
+        .find(|level| level.identifier == reference || level.iid == reference)
+}
+
+pub fn find_level_index(project: &LdtkJson, reference: &str) -> Option<usize> {
+    project
+        .levels
+        .iter()
+        .position(|level| level.identifier == reference || level.iid == reference)
+}
+
+/// The level the current `LevelSelection` points at
+pub fn selected_level<'a>(
+    project: &'a LdtkJson,
+    level_selection: &LevelSelection,
+) -> Option<&'a Level> {
+    project
+        .levels
+        .iter()
+        .enumerate()
+        .find(|(index, level)| level_selection.is_match(index, level))
+        .map(|(_, level)| level)
+}
+
+/// Levels sharing an edge with the given one in the world layout, walkable when streaming
+pub fn neighbour_level_indices(project: &LdtkJson, index: usize) -> Vec<usize> {
+    let Some(level) = project.levels.get(index) else {
+        return Vec::new();
+    };
+
+    let overlap = |start_a: i32, len_a: i32, start_b: i32, len_b: i32| -> bool {
+        start_a < start_b + len_b && start_b < start_a + len_a
+    };
+
+    project
+        .levels
+        .iter()
+        .enumerate()
+        .filter(|(other_index, other)| {
+            let side_by_side = (level.world_x + level.px_wid == other.world_x
+                || other.world_x + other.px_wid == level.world_x)
+                && overlap(level.world_y, level.px_hei, other.world_y, other.px_hei);
+            let stacked = (level.world_y + level.px_hei == other.world_y
+                || other.world_y + other.px_hei == level.world_y)
+                && overlap(level.world_x, level.px_wid, other.world_x, other.px_wid);
+
+            *other_index != index && (side_by_side || stacked)
+        })
+        .map(|(other_index, _)| other_index)
+        .collect()
+}
+
+/// Finds the level whose area in the world layout contains the given point
+pub fn level_index_at(project: &LdtkJson, point: Vec2) -> Option<usize> {
+    project.levels.iter().position(|level| {
+        // LDtk's y axis points down, so the level's world_y is its top edge
+        let left = level.world_x as f32;
+        let top = -level.world_y as f32;
+        let right = left + level.px_wid as f32;
+        let bottom = top - level.px_hei as f32;
+
+        point.x >= left && point.x < right && point.y > bottom && point.y <= top
+    })
+}
+
+fn test_switch_level(
+    level_selection: Res<LevelSelection>,
+    level_graph: Res<LevelGraph>,
+    input: Res<Input<KeyCode>>,
+    ldtk_query: Query<&Handle<LdtkAsset>>,
+    ldtk_assets: Res<Assets<LdtkAsset>>,
+    mut change_writer: EventWriter<ChangeLevelEvent>,
+) {
+    let mut target = None;
+
+    if input.just_pressed(KeyCode::N) {
+        if let LevelSelection::Index(index) = *level_selection {
+            target = level_graph.next_levels(index).first().copied();
+        }
+    }
+
+    let level_keys = [
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+        KeyCode::Key7,
+        KeyCode::Key8,
+        KeyCode::Key9,
+        KeyCode::Key0,
+    ];
+
+    for (index, key) in level_keys.iter().enumerate() {
+        if input.just_pressed(*key) {
+            target = Some(index);
+        }
+    }
+
+    let Some(ldtk) = ldtk_query.get_single().ok().and_then(|handle| ldtk_assets.get(handle)) else {
+        return;
+    };
+
+    // Go through the same path as doors, so the player gets replaced rather than left behind
+    if let Some(level) = target.and_then(|index| ldtk.project.levels.get(index)) {
+        change_writer.send(ChangeLevelEvent {
+            level: Some(level.iid.clone()),
+            entry_door: None,
+            completed: false,
+            win_game: false,
+        });
     }
 }
 
-fn setup_world(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup_world(mut commands: Commands, level_assets: Res<LevelAssets>) {
     commands.spawn(LdtkWorldBundle {
-        ldtk_handle: asset_server.load("levels/World.ldtk"),
+        ldtk_handle: level_assets.world.clone(),
         ..Default::default()
     });
 }
@@ -165,58 +310,140 @@ pub struct WallBundle {
     wall: Wall,
 }
 
+/// What a wall is made of, changing how actors move while standing on it
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Hash, Component)]
+pub enum Surface {
+    #[default]
+    Normal,
+    /// Slippery, slow to speed up and slow to stop
+    Ice,
+    /// Slows actors down and weakens their jumps
+    Mud,
+    /// Launches actors into the air as soon as they land
+    Bounce,
+}
+
+impl Surface {
+    /// How far above a normal jump a bounce pad launches actors
+    pub const BOUNCE_SCALE: f32 = 1.8;
+
+    fn from_int_grid_value(value: i32) -> Self {
+        match value {
+            4 => Surface::Ice,
+            5 => Surface::Mud,
+            6 => Surface::Bounce,
+            _ => Surface::Normal,
+        }
+    }
+
+    pub fn accel_scale(&self) -> f32 {
+        match self {
+            Surface::Ice => 0.15,
+            _ => 1.,
+        }
+    }
+
+    pub fn speed_scale(&self) -> f32 {
+        match self {
+            Surface::Mud => 0.5,
+            _ => 1.,
+        }
+    }
+
+    pub fn jump_scale(&self) -> f32 {
+        match self {
+            Surface::Mud => 0.7,
+            _ => 1.,
+        }
+    }
+
+    /// Drag while not moving, ice barely slows actors down at all
+    pub fn drag_scale(&self) -> f32 {
+        match self {
+            Surface::Ice => 0.05,
+            _ => 1.,
+        }
+    }
+
+    pub fn friction(&self) -> f32 {
+        match self {
+            Surface::Ice => 0.,
+            _ => 1.,
+        }
+    }
+
+    /// Landing sounds are pitched up on ice and down in mud
+    pub fn land_pitch(&self) -> f32 {
+        match self {
+            Surface::Ice => 1.4,
+            Surface::Mud => 0.7,
+            _ => 1.,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default, Bundle)]
-pub struct SpikeBundle {
-    pub collider: Collider,
-    pub label: Labeled,
-    pub active_events: ActiveEvents,
-    pub rotation_constraints: LockedAxes,
-    pub death: crate::player::TouchDeath,
+pub struct SurfaceBundle {
+    wall: Wall,
+    surface: Surface,
 }
 
-impl LdtkIntCell for SpikeBundle {
-    fn bundle_int_cell(_int_grid_cell: IntGridCell, _layer_instance: &LayerInstance) -> Self {
-        let rotation_constraints = LockedAxes::ROTATION_LOCKED;
-
-        SpikeBundle {
-            collider: Collider::cuboid(6., 4.),
-            label: Labeled {
-                name: String::from("spikes"),
-            },
-            rotation_constraints,
-            active_events: ActiveEvents::COLLISION_EVENTS,
-            ..Default::default()
+impl LdtkIntCell for SurfaceBundle {
+    fn bundle_int_cell(int_grid_cell: IntGridCell, _layer_instance: &LayerInstance) -> Self {
+        SurfaceBundle {
+            wall: Wall,
+            surface: Surface::from_int_grid_value(int_grid_cell.value),
         }
     }
 }
 
-#[derive(Clone, Default, Bundle, LdtkEntity)]
-pub struct WheatBundle {
-    pub collider: Collider,
-    pub label: Labeled,
-    pub sensor: Sensor,
-    pub active_events: ActiveEvents,
-    pub rotation_constraints: LockedAxes,
-    pub scythable: Scythable,
-    #[sprite_bundle("sprites/wheat_grown.png")]
-    #[bundle]
-    pub sprite_bundle: SpriteBundle,
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct Spike;
+
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
+pub struct SpikeBundle {
+    spike: Spike,
 }
 
-fn cut_wheat(
-    mut wheat_query: Query<(Entity, &mut Handle<Image>, &Scythable)>,
-    mut commands: Commands,
-    sprites: Res<crate::loading::SpriteAssets>,
-) {
-    for (entity, mut image, scythable) in &mut wheat_query {
-        if scythable.scythed {
-            *image = sprites.texture_wheat_chopped.clone();
+/// Which way a spike points, away from the wall it is attached to
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum SpikeDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
 
-            commands.entity(entity).remove::<Scythable>();
+impl SpikeDirection {
+    /// Points away from the first wall found below, above, left or right of the spike
+    fn from_walls(coords: GridCoords, walls: &HashSet<GridCoords>) -> Self {
+        let GridCoords { x, y } = coords;
+
+        if walls.contains(&GridCoords { x, y: y - 1 }) {
+            SpikeDirection::Up
+        } else if walls.contains(&GridCoords { x, y: y + 1 }) {
+            SpikeDirection::Down
+        } else if walls.contains(&GridCoords { x: x - 1, y }) {
+            SpikeDirection::Right
+        } else if walls.contains(&GridCoords { x: x + 1, y }) {
+            SpikeDirection::Left
+        } else {
+            SpikeDirection::Up
         }
     }
 }
 
+/// The size of each cell in the level's int grid layer
+pub fn level_grid_size(level: &LdtkLevel) -> i32 {
+    let LayerInstance { grid_size, .. } = level
+        .level
+        .layer_instances
+        .clone()
+        .expect("Level asset should have layers")[0];
+
+    grid_size
+}
+
 /// FRom bevy_ecs_ldtk platformer example
 /// Spawns rapier collisions for the walls of a level
 ///
@@ -227,36 +454,17 @@ fn cut_wheat(
 /// Instead, by flagging the wall tiles and spawning the collisions later,
 /// we can minimize the amount of colliding entities.
 ///
-/// The algorithm used here is a nice compromise between simplicity, speed,
-/// and a small number of rectangle colliders.
 /// In basic terms, it will:
 /// 1. consider where the walls are
-/// 2. combine wall tiles into flat "plates" in each individual row
-/// 3. combine the plates into rectangles across multiple rows wherever possible
-/// 4. spawn colliders for each rectangle
+/// 2. combine them into as few rectangles as possible, see `collision::merge_cells`
+/// 3. spawn colliders for each rectangle
 pub fn spawn_wall_collision(
     mut commands: Commands,
-    wall_query: Query<(&GridCoords, &Parent), Added<Wall>>,
+    wall_query: Query<(&GridCoords, &Parent, Option<&Surface>), Added<Wall>>,
     parent_query: Query<&Parent, Without<Wall>>,
     level_query: Query<(Entity, &Handle<LdtkLevel>)>,
     levels: Res<Assets<LdtkLevel>>,
 ) {
-    /// Represents a wide wall that is 1 tile tall
-    /// Used to spawn wall collisions
-    #[derive(Clone, Eq, PartialEq, Debug, Default, Hash)]
-    struct Plate {
-        left: i32,
-        right: i32,
-    }
-
-    /// A simple rectangle type representing a wall of any size
-    struct Rect {
-        left: i32,
-        right: i32,
-        top: i32,
-        bottom: i32,
-    }
-
     // Consider where the walls are
     // storing them as GridCoords in a HashSet for quick, easy lookup
     //
@@ -264,9 +472,12 @@ pub fn spawn_wall_collision(
     // This has two consequences in the resulting collision entities:
     // 1. it forces the walls to be split along level boundaries
     // 2. it lets us easily add the collision entities as children of the appropriate level entity
-    let mut level_to_wall_locations: HashMap<Entity, HashSet<GridCoords>> = HashMap::new();
+    //
+    // Walls are also split up by surface, so actors can tell what they are standing on
+    let mut level_to_wall_locations: HashMap<Entity, HashMap<Surface, HashSet<GridCoords>>> =
+        HashMap::new();
 
-    wall_query.for_each(|(&grid_coords, parent)| {
+    wall_query.for_each(|(&grid_coords, parent, surface)| {
         // An intgrid tile's direct parent will be a layer entity, not the level entity
         // To get the level entity, you need the tile's grandparent.
         // This is where parent_query comes in.
@@ -274,6 +485,8 @@ pub fn spawn_wall_collision(
             level_to_wall_locations
                 .entry(grandparent.get())
                 .or_default()
+                .entry(surface.copied().unwrap_or_default())
+                .or_default()
                 .insert(grid_coords);
         }
     });
@@ -285,105 +498,151 @@ pub fn spawn_wall_collision(
                     .get(level_handle)
                     .expect("Level should be loaded by this point");
 
-                let LayerInstance {
-                    c_wid: width,
-                    c_hei: height,
-                    grid_size,
-                    ..
-                } = level
-                    .level
-                    .layer_instances
-                    .clone()
-                    .expect("Level asset should have layers")[0];
-
-                // combine wall tiles into flat "plates" in each individual row
-                let mut plate_stack: Vec<Vec<Plate>> = Vec::new();
-
-                for y in 0..height {
-                    let mut row_plates: Vec<Plate> = Vec::new();
-                    let mut plate_start = None;
-
-                    // + 1 to the width so the algorithm "terminates" plates that touch the right edge
-                    for x in 0..width + 1 {
-                        match (plate_start, level_walls.contains(&GridCoords { x, y })) {
-                            (Some(s), false) => {
-                                row_plates.push(Plate {
-                                    left: s,
-                                    right: x - 1,
-                                });
-                                plate_start = None;
-                            }
-                            (None, true) => plate_start = Some(x),
-                            _ => (),
-                        }
-                    }
-
-                    plate_stack.push(row_plates);
-                }
-
-                // combine "plates" into rectangles across multiple rows
-                let mut rect_builder: HashMap<Plate, Rect> = HashMap::new();
-                let mut prev_row: Vec<Plate> = Vec::new();
-                let mut wall_rects: Vec<Rect> = Vec::new();
-
-                // an extra empty row so the algorithm "finishes" the rects that touch the top edge
-                plate_stack.push(Vec::new());
-
-                for (y, current_row) in plate_stack.into_iter().enumerate() {
-                    for prev_plate in &prev_row {
-                        if !current_row.contains(prev_plate) {
-                            // remove the finished rect so that the same plate in the future starts a new rect
-                            if let Some(rect) = rect_builder.remove(prev_plate) {
-                                wall_rects.push(rect);
-                            }
-                        }
-                    }
-                    for plate in &current_row {
-                        rect_builder
-                            .entry(plate.clone())
-                            .and_modify(|e| e.top += 1)
-                            .or_insert(Rect {
-                                bottom: y as i32,
-                                top: y as i32,
-                                left: plate.left,
-                                right: plate.right,
-                            });
-                    }
-                    prev_row = current_row;
-                }
+                let grid_size = level_grid_size(level) as f32;
 
                 commands.entity(level_entity).with_children(|level| {
                     // Spawn colliders for every rectangle..
                     // Making the collider a child of the level serves two purposes:
                     // 1. Adjusts the transforms to be relative to the level for free
                     // 2. the colliders will be despawned automatically when levels unload
-                    for wall_rect in wall_rects {
-                        level
-                            .spawn_empty()
-                            .insert(Collider::cuboid(
-                                (wall_rect.right as f32 - wall_rect.left as f32 + 1.)
-                                    * grid_size as f32
-                                    / 2.,
-                                (wall_rect.top as f32 - wall_rect.bottom as f32 + 1.)
-                                    * grid_size as f32
-                                    / 2.,
-                            ))
-                            .insert(RigidBody::Fixed)
-                            .insert(Friction::new(1.0))
-                            .insert(Labeled {
-                                name: String::from("wall"),
-                            })
-                            .insert(Transform::from_xyz(
-                                (wall_rect.left + wall_rect.right + 1) as f32 * grid_size as f32
-                                    / 2.,
-                                (wall_rect.bottom + wall_rect.top + 1) as f32 * grid_size as f32
-                                    / 2.,
-                                0.,
-                            ))
-                            .insert(GlobalTransform::default());
+                    for (&surface, walls) in level_walls {
+                        for wall_rect in merge_cells(walls) {
+                            level
+                                .spawn_empty()
+                                .insert(Collider::cuboid(
+                                    wall_rect.width() as f32 * grid_size / 2.,
+                                    wall_rect.height() as f32 * grid_size / 2.,
+                                ))
+                                .insert(RigidBody::Fixed)
+                                .insert(Friction::new(surface.friction()))
+                                .insert(surface)
+                                .insert(Labeled {
+                                    name: String::from("wall"),
+                                })
+                                .insert(Transform::from_xyz(
+                                    (wall_rect.left + wall_rect.right + 1) as f32 * grid_size / 2.,
+                                    (wall_rect.bottom + wall_rect.top + 1) as f32 * grid_size / 2.,
+                                    0.,
+                                ))
+                                .insert(GlobalTransform::default());
+                        }
                     }
                 });
             }
         });
     }
 }
+
+/// Spawns merged hitboxes for spikes the same way as `spawn_wall_collision`,
+/// split up by which way the spikes point so each hitbox hugs the wall they sit on
+pub fn spawn_spike_collision(
+    mut commands: Commands,
+    spike_query: Query<(&GridCoords, &Parent), Added<Spike>>,
+    wall_query: Query<(&GridCoords, &Parent), With<Wall>>,
+    parent_query: Query<&Parent, (Without<Wall>, Without<Spike>)>,
+    level_query: Query<(Entity, &Handle<LdtkLevel>)>,
+    levels: Res<Assets<LdtkLevel>>,
+) {
+    /// Gap left between the ends of a row of spikes and the next tile
+    const SPIKE_MARGIN: f32 = 2.;
+
+    if spike_query.is_empty() {
+        return;
+    }
+
+    let level_of = |parent: &Parent| {
+        parent_query
+            .get(parent.get())
+            .ok()
+            .map(|grandparent| grandparent.get())
+    };
+
+    let mut level_to_wall_locations: HashMap<Entity, HashSet<GridCoords>> = HashMap::new();
+
+    for (&grid_coords, parent) in &wall_query {
+        if let Some(level_entity) = level_of(parent) {
+            level_to_wall_locations
+                .entry(level_entity)
+                .or_default()
+                .insert(grid_coords);
+        }
+    }
+
+    let mut level_to_spike_locations: HashMap<
+        Entity,
+        HashMap<SpikeDirection, HashSet<GridCoords>>,
+    > = HashMap::new();
+    let no_walls = HashSet::new();
+
+    for (&grid_coords, parent) in &spike_query {
+        if let Some(level_entity) = level_of(parent) {
+            let walls = level_to_wall_locations
+                .get(&level_entity)
+                .unwrap_or(&no_walls);
+
+            level_to_spike_locations
+                .entry(level_entity)
+                .or_default()
+                .entry(SpikeDirection::from_walls(grid_coords, walls))
+                .or_default()
+                .insert(grid_coords);
+        }
+    }
+
+    for (level_entity, level_handle) in &level_query {
+        let Some(level_spikes) = level_to_spike_locations.get(&level_entity) else {
+            continue;
+        };
+
+        let level = levels
+            .get(level_handle)
+            .expect("Level should be loaded by this point");
+
+        let grid_size = level_grid_size(level) as f32;
+
+        commands.entity(level_entity).with_children(|level| {
+            for (direction, spikes) in level_spikes {
+                for spike_rect in merge_cells(spikes) {
+                    let mut left = spike_rect.left as f32 * grid_size;
+                    let mut right = (spike_rect.right + 1) as f32 * grid_size;
+                    let mut bottom = spike_rect.bottom as f32 * grid_size;
+                    let mut top = (spike_rect.top + 1) as f32 * grid_size;
+
+                    // Spikes are half a tile tall, standing on the side facing away from where they point
+                    match direction {
+                        SpikeDirection::Up => top -= grid_size / 2.,
+                        SpikeDirection::Down => bottom += grid_size / 2.,
+                        SpikeDirection::Right => right -= grid_size / 2.,
+                        SpikeDirection::Left => left += grid_size / 2.,
+                    }
+
+                    match direction {
+                        SpikeDirection::Up | SpikeDirection::Down => {
+                            left += SPIKE_MARGIN;
+                            right -= SPIKE_MARGIN;
+                        }
+                        SpikeDirection::Left | SpikeDirection::Right => {
+                            bottom += SPIKE_MARGIN;
+                            top -= SPIKE_MARGIN;
+                        }
+                    }
+
+                    level
+                        .spawn_empty()
+                        .insert(Collider::cuboid((right - left) / 2., (top - bottom) / 2.))
+                        .insert(ActiveEvents::COLLISION_EVENTS)
+                        .insert(crate::player::TouchDeath)
+                        .insert(Labeled {
+                            name: String::from("spikes"),
+                        })
+                        .insert(Transform::from_xyz(
+                            (left + right) / 2.,
+                            (bottom + top) / 2.,
+                            0.,
+                        ))
+                        .insert(GlobalTransform::default());
+                }
+            }
+        });
+    }
+}
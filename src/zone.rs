@@ -0,0 +1,182 @@
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+use crate::actor::{actor_movement, actor_status, ActorStatus};
+use crate::world::Labeled;
+use crate::GameState;
+
+pub struct ZonePlugin;
+
+/// An area of the level that pushes, floats or lightens whatever is inside it
+#[derive(Component, Clone, Debug)]
+pub struct ForceZone {
+    pub kind: ZoneKind,
+    pub half_extents: Vec2,
+}
+
+#[derive(Clone, Debug)]
+pub enum ZoneKind {
+    Wind { velocity: Vec2 },
+    Water { buoyancy: f32, drag: f32 },
+    LowGravity { scale: f32 },
+}
+
+impl Default for ForceZone {
+    fn default() -> Self {
+        ForceZone {
+            kind: ZoneKind::LowGravity { scale: 1. },
+            half_extents: Vec2::ZERO,
+        }
+    }
+}
+
+/// Everything the zones at one spot do, combined
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ZoneEffects {
+    /// Drift on top of an actor's own movement
+    pub wind: Vec2,
+    pub gravity_scale: f32,
+    pub water: Option<Water>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Water {
+    pub buoyancy: f32,
+    pub drag: f32,
+}
+
+impl Default for ZoneEffects {
+    fn default() -> Self {
+        ZoneEffects {
+            wind: Vec2::ZERO,
+            gravity_scale: 1.,
+            water: None,
+        }
+    }
+}
+
+impl ZoneEffects {
+    /// Swimming up is a weaker, repeatable jump
+    pub const SWIM_SCALE: f32 = 0.6;
+
+    /// Adds up every zone that contains the position
+    pub fn at<'a>(
+        position: Vec2,
+        zones: impl IntoIterator<Item = (&'a GlobalTransform, &'a ForceZone)>,
+    ) -> Self {
+        let mut effects = ZoneEffects::default();
+
+        for (transform, zone) in zones {
+            let offset = position - transform.translation().truncate();
+            if offset.x.abs() > zone.half_extents.x || offset.y.abs() > zone.half_extents.y {
+                continue;
+            }
+
+            match zone.kind {
+                ZoneKind::Wind { velocity } => effects.wind += velocity,
+                ZoneKind::Water { buoyancy, drag } => {
+                    effects.water = Some(Water { buoyancy, drag });
+                }
+                ZoneKind::LowGravity { scale } => effects.gravity_scale *= scale,
+            }
+        }
+
+        effects
+    }
+}
+
+#[derive(Clone, Default, Bundle)]
+pub struct ForceZoneBundle {
+    #[bundle]
+    pub sprite_bundle: SpriteBundle,
+    pub label: Labeled,
+    pub zone: ForceZone,
+}
+
+impl Plugin for ZonePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Playing)
+                .with_system(actor_zones)
+                .after(actor_status)
+                .before(actor_movement),
+        );
+    }
+}
+
+impl LdtkEntity for ForceZoneBundle {
+    fn bundle_entity(
+        entity_instance: &EntityInstance,
+        _layer_instance: &LayerInstance,
+        _tileset: Option<&Handle<Image>>,
+        _tileset_definition: Option<&TilesetDefinition>,
+        _asset_server: &AssetServer,
+        _texture_atlases: &mut Assets<TextureAtlas>,
+    ) -> Self {
+        let size = Vec2::new(entity_instance.width as f32, entity_instance.height as f32);
+
+        let mut angle = 0.;
+        let mut strength = 120.;
+        let mut buoyancy = 420.;
+        let mut drag = 4.;
+        let mut gravity_scale = 0.4;
+
+        for field in entity_instance.field_instances.iter() {
+            match (field.identifier.as_str(), &field.value) {
+                ("Angle", FieldValue::Float(Some(value))) => angle = *value,
+                ("Strength", FieldValue::Float(Some(value))) => strength = *value,
+                ("Buoyancy", FieldValue::Float(Some(value))) => buoyancy = *value,
+                ("Drag", FieldValue::Float(Some(value))) => drag = *value,
+                ("GravityScale", FieldValue::Float(Some(value))) => gravity_scale = *value,
+                (unknown, _) => println!("Unknown field \"{}\" on LDtk zone object!", unknown),
+            }
+        }
+
+        let (kind, color) = match entity_instance.identifier.as_str() {
+            "Wind" => (
+                ZoneKind::Wind {
+                    velocity: Vec2::from_angle(angle.to_radians()) * strength,
+                },
+                Color::rgba(0.78, 0.86, 0.82, 0.15),
+            ),
+            "Water" => (
+                ZoneKind::Water { buoyancy, drag },
+                Color::rgba(0.3, 0.61, 0.9, 0.4),
+            ),
+            _ => (
+                ZoneKind::LowGravity {
+                    scale: gravity_scale,
+                },
+                Color::rgba(0.66, 0.52, 0.95, 0.15),
+            ),
+        };
+
+        ForceZoneBundle {
+            sprite_bundle: SpriteBundle {
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                transform: Transform::from_translation(Vec3::new(0., 0., 0.8)),
+                ..Default::default()
+            },
+            label: Labeled {
+                name: entity_instance.identifier.to_lowercase(),
+            },
+            zone: ForceZone {
+                kind,
+                half_extents: size / 2.,
+            },
+        }
+    }
+}
+
+fn actor_zones(
+    mut actor_query: Query<(&GlobalTransform, &mut ActorStatus)>,
+    zone_query: Query<(&GlobalTransform, &ForceZone)>,
+) {
+    for (transform, mut status) in &mut actor_query {
+        status.zone = ZoneEffects::at(transform.translation().truncate(), &zone_query);
+    }
+}